@@ -1,7 +1,10 @@
 from notifykit._filters import EventFilter, CommonFilter
-from notifykit._notifier import Notifier, NotifierT
+from notifykit._notifier import Notifier, NotifierT, Subscription
+from notifykit._snapshot import Snapshot
 from notifykit._notifykit_lib import (
     __version__,
+    WatcherError,
+    EventsLost,
     ObjectType,
     AccessType,
     AccessMode,
@@ -11,24 +14,36 @@ from notifykit._notifykit_lib import (
     ModifyOtherEvent,
     ModifyUnknownEvent,
     RenameEvent,
+    MovedInEvent,
+    MovedOutEvent,
+    RescanEvent,
+    DirectoryChangedEvent,
     DataType,
     MetadataType,
     DeleteEvent,
     CreateEvent,
+    event_from_dict,
+    event_from_json,
 )
 
 from notifykit._testing import NotifierMock
-from notifykit._typing import Event
+from notifykit._typing import BackpressurePolicy, DeliveryMode, Event
 
 VERSION = __version__
 
 __all__ = (
     "Notifier",
     "NotifierT",
+    "Subscription",
+    "Snapshot",
     "VERSION",
+    "WatcherError",
+    "EventsLost",
     "EventFilter",
     "CommonFilter",
     "Event",
+    "BackpressurePolicy",
+    "DeliveryMode",
     "ObjectType",
     "AccessType",
     "AccessMode",
@@ -38,8 +53,14 @@ __all__ = (
     "ModifyOtherEvent",
     "ModifyUnknownEvent",
     "RenameEvent",
+    "MovedInEvent",
+    "MovedOutEvent",
+    "RescanEvent",
+    "DirectoryChangedEvent",
     "DataType",
     "MetadataType",
+    "event_from_dict",
+    "event_from_json",
     "NotifierMock",
     "DeleteEvent",
     "CreateEvent",
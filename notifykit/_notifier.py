@@ -1,12 +1,14 @@
+from contextlib import contextmanager
 from os import PathLike
 import logging
-from typing import Sequence, Protocol, Optional, List
+from types import TracebackType
+from typing import ContextManager, Iterator, Sequence, Protocol, Optional, List, Tuple, Type
 from notifykit._notifykit_lib import (
     WatcherWrapper,
     EventBatchIter,
 )
 
-from notifykit._typing import Event
+from notifykit._typing import BackpressurePolicy, DeliveryMode, Event
 from notifykit._filters import EventFilter
 
 logger = logging.getLogger(__name__)
@@ -19,11 +21,57 @@ class NotifierT(Protocol):
 
     async def unwatch(self, paths: Sequence[PathLike[str]]) -> None: ...
 
+    def suppress(self, paths: Sequence[PathLike[str]]) -> ContextManager[None]: ...
+
+    def expect_write(self, paths: Sequence[PathLike[str]], timeout_ms: int = 1000) -> None: ...
+
     def __aiter__(self) -> "NotifierT": ...
 
     async def __anext__(self) -> List[Event]: ...
 
-    def stop(self) -> None: ...
+    def stop(self, flush: bool = False) -> None: ...
+
+    def close(self) -> None: ...
+
+    async def __aenter__(self) -> "NotifierT": ...
+
+    async def __aexit__(
+        self,
+        exc_type: Optional[Type[BaseException]],
+        exc_val: Optional[BaseException],
+        exc_tb: Optional[TracebackType],
+    ) -> None: ...
+
+
+class Subscription:
+    """
+    An independent stream of events with its own filter and debouncing settings
+    that shares the filesystem watcher with its notifier
+    """
+
+    def __init__(self, watcher: WatcherWrapper, name: str, events: EventBatchIter) -> None:
+        self._watcher = watcher
+        self._name = name
+        self._aiter = events
+
+    @property
+    def name(self) -> str:
+        return self._name
+
+    def __aiter__(self) -> "Subscription":
+        return self
+
+    async def __anext__(self) -> List[Event]:
+        return await self._aiter.__anext__()
+
+    def close(self) -> None:
+        """
+        Stop delivering events to this subscription and end its iteration
+        """
+        self._watcher.unsubscribe(self._name)
+
+    def __repr__(self) -> str:
+        return f"{self.__class__.__name__}(name={self._name!r})"
 
 
 class Notifier:
@@ -39,22 +87,43 @@ class Notifier:
         debug: bool = False,
         follow_symlinks: bool = True,
         filter: Optional[EventFilter] = None,
+        backpressure: BackpressurePolicy = "drop_oldest",
+        mode: DeliveryMode = "delay",
+        max_wait_ms: Optional[int] = None,
+        collapse: bool = False,
+        max_batch_size: Optional[int] = None,
+        storm_threshold: Optional[int] = None,
+        aggregate_threshold: Optional[int] = None,
+        expand_dir_renames: bool = False,
+        expand_dir_deletes: bool = False,
+        ignore_noise: bool = False,
     ) -> None:
         self._debounce_ms = debounce_ms
         self._tick_ms = tick_ms
+        self._event_buffer_size = event_buffer_size
+        self._backpressure = backpressure
+        self._mode = mode
+        self._max_wait_ms = max_wait_ms
+        self._collapse = collapse
+        self._max_batch_size = max_batch_size
+        self._storm_threshold = storm_threshold
+        self._aggregate_threshold = aggregate_threshold
         self._debug = debug
 
-        self._watcher = WatcherWrapper(debounce_ms, event_buffer_size, debug, follow_symlinks)
+        self._watcher = WatcherWrapper(
+            debounce_ms,
+            event_buffer_size,
+            debug,
+            follow_symlinks,
+            backpressure,
+            mode,
+            max_wait_ms,
+            expand_dir_renames,
+            expand_dir_deletes,
+            ignore_noise,
+        )
 
-        # Extract filter config to pass to the Rust side
-        if filter is not None:
-            self._ignore_dirs = list(filter.ignore_dirs)
-            self._ignore_patterns = list(filter.ignore_object_patterns)
-            self._ignore_paths = [str(p) for p in filter.ignore_paths]
-        else:
-            self._ignore_dirs = []
-            self._ignore_patterns = []
-            self._ignore_paths = []
+        self._ignore_dirs, self._ignore_patterns, self._ignore_paths = _filter_config(filter)
 
         self._aiter: Optional[EventBatchIter] = None  # created lazily from Rust iterator
 
@@ -69,28 +138,144 @@ class Notifier:
     async def unwatch(self, paths: Sequence[PathLike[str]]) -> None:
         await self._watcher.unwatch([str(path) for path in paths])
 
+    def subscribe(
+        self,
+        name: str,
+        debounce_ms: Optional[int] = None,
+        tick_ms: Optional[int] = None,
+        event_buffer_size: Optional[int] = None,
+        filter: Optional[EventFilter] = None,
+        backpressure: Optional[BackpressurePolicy] = None,
+        mode: Optional[DeliveryMode] = None,
+        max_wait_ms: Optional[int] = None,
+        collapse: Optional[bool] = None,
+        max_batch_size: Optional[int] = None,
+        storm_threshold: Optional[int] = None,
+        aggregate_threshold: Optional[int] = None,
+    ) -> Subscription:
+        """
+        Create a named subscription that receives events from the same watched paths,
+        but applies its own filter, debouncing and buffer size.
+        Unset settings are inherited from the notifier (except the filter)
+        """
+        ignore_dirs, ignore_patterns, ignore_paths = _filter_config(filter)
+
+        events = self._watcher.subscribe(
+            name,
+            self._debounce_ms if debounce_ms is None else debounce_ms,
+            self._tick_ms if tick_ms is None else tick_ms,
+            self._event_buffer_size if event_buffer_size is None else event_buffer_size,
+            self._backpressure if backpressure is None else backpressure,
+            self._mode if mode is None else mode,
+            self._max_wait_ms if max_wait_ms is None else max_wait_ms,
+            ignore_dirs,
+            ignore_patterns,
+            ignore_paths,
+            collapse=self._collapse if collapse is None else collapse,
+            max_batch_size=self._max_batch_size if max_batch_size is None else max_batch_size,
+            storm_threshold=self._storm_threshold if storm_threshold is None else storm_threshold,
+            aggregate_threshold=self._aggregate_threshold if aggregate_threshold is None else aggregate_threshold,
+        )
+
+        return Subscription(self._watcher, name, events)
+
+    @contextmanager
+    def suppress(self, paths: Sequence[PathLike[str]]) -> Iterator[None]:
+        """
+        Drop events caused by the application's own writes to the given paths (directories include their children)
+        made inside the context block
+        """
+        suppress_id = self._watcher.suppress([str(path) for path in paths])
+
+        try:
+            yield
+        finally:
+            self._watcher.release(suppress_id)
+
+    def expect_write(self, paths: Sequence[PathLike[str]], timeout_ms: int = 1000) -> None:
+        """
+        Drop events for the given paths that arrive within the next timeout_ms milliseconds
+        """
+        self._watcher.expect_write([str(path) for path in paths], timeout_ms)
+
+    def save_snapshot(self, path: PathLike[str]) -> None:
+        """
+        Save the state of the watched paths (file IDs, sizes and modification times) to the given file,
+        so changes made while the application is not running can be found with changes_since()
+        """
+        self._watcher.save_snapshot(str(path))
+
+    def changes_since(self, path: PathLike[str]) -> List[Event]:
+        """
+        Compare the watched paths with the snapshot saved by save_snapshot() and
+        return the create, delete, modify and rename events that turn the snapshot into the current state
+        """
+        return self._watcher.changes_since(str(path))
+
+    def record(self, path: PathLike[str]) -> None:
+        """
+        Record the raw events of the backend to the given file until the notifier is closed,
+        so issues with debouncing or filtering can be reproduced with `notifykit replay`.
+        The file should be outside of the watched paths, otherwise the recording records its own writes
+        """
+        self._watcher.record(str(path))
+
     def __aiter__(self) -> "Notifier":
         # start/attach the async iterator from Rust; safe to do before watch()
         if self._aiter is None:
-            self._aiter = self._watcher.events(
-                self._tick_ms,
-                self._ignore_dirs,
-                self._ignore_patterns,
-                self._ignore_paths,
-            ).__aiter__()
+            self._aiter = self._events()
 
         return self
 
     async def __anext__(self) -> List[Event]:
         if self._aiter is None:
-            self._aiter = self._watcher.events(
-                self._tick_ms,
-                self._ignore_dirs,
-                self._ignore_patterns,
-                self._ignore_paths,
-            ).__aiter__()
+            self._aiter = self._events()
 
         return await self._aiter.__anext__()
 
-    def stop(self) -> None:
-        self._watcher.stop()
+    def _events(self) -> EventBatchIter:
+        return self._watcher.events(
+            self._tick_ms,
+            self._ignore_dirs,
+            self._ignore_patterns,
+            self._ignore_paths,
+            collapse=self._collapse,
+            max_batch_size=self._max_batch_size,
+            storm_threshold=self._storm_threshold,
+            aggregate_threshold=self._aggregate_threshold,
+        ).__aiter__()
+
+    def stop(self, flush: bool = False) -> None:
+        """
+        Stop watching and end the event iteration.
+        If flush is True, events still pending in the debounce window are delivered as the final batch
+        """
+        self._watcher.stop(flush)
+
+    def close(self) -> None:
+        """
+        Stop watching, unwatch all paths and release the underlying watcher (its thread and kernel resources).
+        The notifier can't be used after it's closed
+        """
+        self._watcher.close()
+
+    async def __aenter__(self) -> "Notifier":
+        return self
+
+    async def __aexit__(
+        self,
+        exc_type: Optional[Type[BaseException]],
+        exc_val: Optional[BaseException],
+        exc_tb: Optional[TracebackType],
+    ) -> None:
+        self.close()
+
+
+def _filter_config(filter: Optional[EventFilter]) -> Tuple[List[str], List[str], List[str]]:
+    """
+    Extract filter config to pass to the Rust side
+    """
+    if filter is None:
+        return [], [], []
+
+    return list(filter.ignore_dirs), list(filter.ignore_object_patterns), [str(p) for p in filter.ignore_paths]
@@ -1,6 +1,6 @@
 from enum import IntEnum
-from typing import List, Optional
-from notifykit._typing import Event
+from typing import Any, Dict, List, Optional, Tuple
+from notifykit._typing import BackpressurePolicy, DeliveryMode, Event
 
 """
 The lib version
@@ -10,6 +10,11 @@ __version__: str
 class WatcherError(Exception):
     """Watcher Runtime Error"""
 
+class EventsLost(WatcherError):
+    """The consumer fell behind and event batches were dropped (raised with the `raise` backpressure policy)"""
+
+    count: int
+
 # Main Event Groups
 
 class ObjectType(IntEnum):
@@ -53,46 +58,180 @@ class AccessEvent:
     access_mode: Optional[AccessMode]
 
     def __init__(self, path: str, access_type: AccessType, access_mode: Optional[AccessMode]) -> None: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_dict(data: Dict[str, Any]) -> "AccessEvent": ...
+    @staticmethod
+    def from_json(data: str) -> "AccessEvent": ...
 
 class CreateEvent:
     path: str
     file_type: ObjectType
 
     def __init__(self, path: str, file_type: ObjectType) -> None: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_dict(data: Dict[str, Any]) -> "CreateEvent": ...
+    @staticmethod
+    def from_json(data: str) -> "CreateEvent": ...
 
 class ModifyDataEvent:
     path: str
     data_type: DataType
 
     def __init__(self, path: str, data_type: DataType) -> None: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_dict(data: Dict[str, Any]) -> "ModifyDataEvent": ...
+    @staticmethod
+    def from_json(data: str) -> "ModifyDataEvent": ...
 
 class ModifyMetadataEvent:
     path: str
     metadata_type: MetadataType
 
     def __init__(self, path: str, metadata_type: MetadataType) -> None: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_dict(data: Dict[str, Any]) -> "ModifyMetadataEvent": ...
+    @staticmethod
+    def from_json(data: str) -> "ModifyMetadataEvent": ...
 
 class ModifyOtherEvent:
     path: str
 
     def __init__(self, path: str) -> None: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_dict(data: Dict[str, Any]) -> "ModifyOtherEvent": ...
+    @staticmethod
+    def from_json(data: str) -> "ModifyOtherEvent": ...
 
 class ModifyUnknownEvent:
     path: str
 
     def __init__(self, path: str) -> None: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_dict(data: Dict[str, Any]) -> "ModifyUnknownEvent": ...
+    @staticmethod
+    def from_json(data: str) -> "ModifyUnknownEvent": ...
 
 class DeleteEvent:
     path: str
     file_type: ObjectType
 
     def __init__(self, path: str, file_type: ObjectType) -> None: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_dict(data: Dict[str, Any]) -> "DeleteEvent": ...
+    @staticmethod
+    def from_json(data: str) -> "DeleteEvent": ...
 
 class RenameEvent:
     old_path: str
     new_path: str
+    is_dir: bool
+    children: List[Tuple[str, str]]
+    """Old and new paths of the renamed directory's children (when expand_dir_renames is enabled)"""
+
+    def __init__(
+        self,
+        old_path: str,
+        new_path: str,
+        is_dir: bool = False,
+        children: List[Tuple[str, str]] = ...,
+    ) -> None: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_dict(data: Dict[str, Any]) -> "RenameEvent": ...
+    @staticmethod
+    def from_json(data: str) -> "RenameEvent": ...
+
+class MovedInEvent:
+    """
+    A file was moved into the watched paths from a location that is not watched
+    """
+
+    path: str
+
+    def __init__(self, path: str) -> None: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_dict(data: Dict[str, Any]) -> "MovedInEvent": ...
+    @staticmethod
+    def from_json(data: str) -> "MovedInEvent": ...
 
-    def __init__(self, old_path: str, new_path: str) -> None: ...
+class MovedOutEvent:
+    """
+    A file was moved out of the watched paths to a location that is not watched
+    """
+
+    path: str
+
+    def __init__(self, path: str) -> None: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_dict(data: Dict[str, Any]) -> "MovedOutEvent": ...
+    @staticmethod
+    def from_json(data: str) -> "MovedOutEvent": ...
+
+class RescanEvent:
+    """
+    Too many changes happened under the path at once (more than storm_threshold events in a batch),
+    so the subtree should be rescanned instead of relying on individual events
+    """
+
+    path: str
+    count: int
+
+    def __init__(self, path: str, count: int) -> None: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_dict(data: Dict[str, Any]) -> "RescanEvent": ...
+    @staticmethod
+    def from_json(data: str) -> "RescanEvent": ...
+
+class DirectoryChangedEvent:
+    """
+    More than aggregate_threshold direct children of the directory changed within one batch,
+    so their events were rolled up into this one (changes deeper in the tree count towards their own directory)
+    """
+
+    path: str
+    count: int
+    kinds: List[str]
+
+    def __init__(self, path: str, count: int, kinds: List[str]) -> None: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_dict(data: Dict[str, Any]) -> "DirectoryChangedEvent": ...
+    @staticmethod
+    def from_json(data: str) -> "DirectoryChangedEvent": ...
+
+def event_from_dict(data: Dict[str, Any]) -> Event:
+    """
+    Read an event of any kind from a dict produced by to_dict().
+    Events are dicts with the "kind" of the event (e.g. "create", "modify_data", "rename") and the event fields,
+    where paths are strings and enum values are names (e.g. {"kind": "create", "path": "/a.txt", "file_type": "FILE"})
+    """
+
+def event_from_json(data: str) -> Event:
+    """
+    Read an event of any kind from JSON produced by to_json()
+    """
 
 class EventBatchIter:
     def __aiter__(self) -> "EventBatchIter": ...
@@ -111,6 +250,12 @@ class WatcherWrapper:
         event_buffer_size: int = 1024,
         debug: bool = False,
         follow_symlinks: bool = True,
+        backpressure: BackpressurePolicy = "drop_oldest",
+        mode: DeliveryMode = "delay",
+        max_wait_ms: Optional[int] = None,
+        expand_dir_renames: bool = False,
+        expand_dir_deletes: bool = False,
+        ignore_noise: bool = False,
     ) -> None: ...
     async def watch(self, paths: List[str], recursive: bool = True, ignore_permission_errors: bool = False) -> None: ...
     async def unwatch(self, paths: List[str]) -> None: ...
@@ -120,5 +265,53 @@ class WatcherWrapper:
         ignore_dirs: List[str] = ...,
         ignore_patterns: List[str] = ...,
         ignore_paths: List[str] = ...,
+        collapse: bool = False,
+        max_batch_size: Optional[int] = None,
+        storm_threshold: Optional[int] = None,
+        aggregate_threshold: Optional[int] = None,
     ) -> EventBatchIter: ...
-    def stop(self) -> None: ...
+    def subscribe(
+        self,
+        name: str,
+        debounce_ms: int,
+        tick_ms: int,
+        event_buffer_size: int,
+        backpressure: BackpressurePolicy,
+        mode: DeliveryMode,
+        max_wait_ms: Optional[int] = None,
+        ignore_dirs: List[str] = ...,
+        ignore_patterns: List[str] = ...,
+        ignore_paths: List[str] = ...,
+        collapse: bool = False,
+        max_batch_size: Optional[int] = None,
+        storm_threshold: Optional[int] = None,
+        aggregate_threshold: Optional[int] = None,
+    ) -> EventBatchIter: ...
+    def unsubscribe(self, name: str) -> None: ...
+    def suppress(self, paths: List[str]) -> int: ...
+    def release(self, suppress_id: int) -> None: ...
+    def expect_write(self, paths: List[str], timeout_ms: int) -> None: ...
+    def save_snapshot(self, path: str) -> None: ...
+    def changes_since(self, path: str) -> List[Event]: ...
+    def record(self, path: str) -> None: ...
+    def stop(self, flush: bool = False) -> None: ...
+    def close(self) -> None: ...
+
+class SnapshotWrapper:
+    """
+    Paths under a root along with their file IDs, sizes and modification times at some point in time
+    """
+
+    @staticmethod
+    def take(
+        root: str,
+        recursive: bool = True,
+        ignore_dirs: List[str] = ...,
+        ignore_patterns: List[str] = ...,
+        ignore_paths: List[str] = ...,
+    ) -> "SnapshotWrapper": ...
+    @staticmethod
+    def load(path: str) -> "SnapshotWrapper": ...
+    def save(self, path: str) -> None: ...
+    def diff(self, other: "SnapshotWrapper") -> List[Event]: ...
+    def __len__(self) -> int: ...
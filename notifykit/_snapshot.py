@@ -0,0 +1,52 @@
+from os import PathLike
+from typing import List, Optional
+
+from notifykit._filters import EventFilter
+from notifykit._notifier import _filter_config
+from notifykit._notifykit_lib import SnapshotWrapper
+from notifykit._typing import Event
+
+
+class Snapshot:
+    """
+    Snapshot captures paths under a directory along with their file IDs, sizes and modification times,
+    so it can be compared with another snapshot of the same directory taken later on
+    """
+
+    def __init__(self, snapshot: SnapshotWrapper) -> None:
+        self._snapshot = snapshot
+
+    @classmethod
+    def take(cls, root: PathLike[str], recursive: bool = True, filter: Optional[EventFilter] = None) -> "Snapshot":
+        """
+        Walk the directory and capture the current state of its paths.
+        Paths matching the filter (and their children) are skipped
+        """
+        ignore_dirs, ignore_patterns, ignore_paths = _filter_config(filter)
+
+        return cls(SnapshotWrapper.take(str(root), recursive, ignore_dirs, ignore_patterns, ignore_paths))
+
+    @classmethod
+    def load(cls, path: PathLike[str]) -> "Snapshot":
+        """
+        Read a snapshot saved with save()
+        """
+        return cls(SnapshotWrapper.load(str(path)))
+
+    def save(self, path: PathLike[str]) -> None:
+        """
+        Write the snapshot to the given file
+        """
+        self._snapshot.save(str(path))
+
+    def diff(self, other: "Snapshot") -> List[Event]:
+        """
+        Return the create, delete, modify and rename events that turn this snapshot into the other (newer) one
+        """
+        return self._snapshot.diff(other._snapshot)
+
+    def __len__(self) -> int:
+        return len(self._snapshot)
+
+    def __repr__(self) -> str:
+        return f"{self.__class__.__name__}(paths={len(self)})"
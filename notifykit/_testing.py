@@ -1,5 +1,7 @@
+from contextlib import contextmanager
 from os import PathLike
-from typing import Sequence, List, Optional
+from types import TracebackType
+from typing import Iterator, Sequence, List, Optional, Type
 
 from notifykit._typing import Event
 
@@ -14,12 +16,17 @@ class NotifierMock:
         events_batches: Optional[List[List[Event]]] = None,
     ) -> None:
         self._watch_paths: List[PathLike[str]] = []
+        self._suppressed_paths: List[PathLike[str]] = []
         self._events_batches = events_batches or []
 
     @property
     def watch_paths(self) -> List[PathLike[str]]:
         return self._watch_paths
 
+    @property
+    def suppressed_paths(self) -> List[PathLike[str]]:
+        return self._suppressed_paths
+
     @property
     def events_batches(self) -> List[List[Event]]:
         return self._events_batches
@@ -39,6 +46,14 @@ class NotifierMock:
         for path in paths:
             self._watch_paths.remove(path)
 
+    @contextmanager
+    def suppress(self, paths: Sequence[PathLike[str]]) -> Iterator[None]:
+        self._suppressed_paths.extend(paths)
+        yield
+
+    def expect_write(self, paths: Sequence[PathLike[str]], timeout_ms: int = 1000) -> None:
+        self._suppressed_paths.extend(paths)
+
     def __aiter__(self) -> "NotifierMock":
         return self
 
@@ -57,5 +72,21 @@ class NotifierMock:
 
         return self._events_batches.pop(0)
 
-    def stop(self) -> None:
+    def stop(self, flush: bool = False) -> None:
+        if not flush:
+            self._events_batches.clear()
+
+    def close(self) -> None:
+        self._watch_paths.clear()
         self._events_batches.clear()
+
+    async def __aenter__(self) -> "NotifierMock":
+        return self
+
+    async def __aexit__(
+        self,
+        exc_type: Optional[Type[BaseException]],
+        exc_val: Optional[BaseException],
+        exc_tb: Optional[TracebackType],
+    ) -> None:
+        self.close()
@@ -1,6 +1,6 @@
 from __future__ import annotations
 
-from typing import Union
+from typing import Literal, Union
 
 from notifykit._notifykit_lib import (
     AccessEvent,
@@ -11,6 +11,10 @@ from notifykit._notifykit_lib import (
     ModifyUnknownEvent,
     DeleteEvent,
     RenameEvent,
+    MovedInEvent,
+    MovedOutEvent,
+    RescanEvent,
+    DirectoryChangedEvent,
 )
 
 Event = Union[
@@ -22,4 +26,24 @@ Event = Union[
     ModifyUnknownEvent,
     DeleteEvent,
     RenameEvent,
+    MovedInEvent,
+    MovedOutEvent,
+    RescanEvent,
+    DirectoryChangedEvent,
 ]
+
+BackpressurePolicy = Literal["drop_oldest", "block", "coalesce", "raise"]
+"""
+What happens when the consumer falls behind and the event buffer is full:
+drop the oldest batches (drop_oldest), wait for the consumer (block),
+merge pending batches into one deduplicated batch (coalesce), or raise EventsLost (raise)
+"""
+
+DeliveryMode = Literal["delay", "debounce", "leading", "throttle"]
+"""
+When buffered events are delivered:
+each event after it's been buffered for debounce_ms (delay),
+events of a path once it has had no new events for debounce_ms (debounce),
+the first event of a path right away, dropping its further events for debounce_ms (leading),
+or all pending events at most once per debounce_ms (throttle)
+"""
@@ -0,0 +1,174 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::events::EventType;
+use crate::events::directory::DirectoryChangedEvent;
+
+/// Events of one directory's entries within a batch
+#[derive(Debug, Default)]
+struct Group {
+    count: usize,
+    kinds: BTreeSet<&'static str>,
+}
+
+/// Returns the directory the event is rolled up into, which is always the direct parent of its path.
+///
+/// Renames are rolled up only when both their paths are in the same directory.
+fn parent_dir(event: &EventType) -> Option<&Path> {
+    match event {
+        EventType::Rename(e) => e.new_path.parent().filter(|dir| e.old_path.parent() == Some(*dir)),
+        EventType::Rescan(_) | EventType::DirectoryChanged(_) => None,
+        _ => event.path().and_then(Path::parent),
+    }
+}
+
+/// Roll up events of directories with more than `threshold` changed direct children in the batch
+/// into a single `DirectoryChangedEvent` per directory.
+///
+/// Only direct children are counted: changes deeper in the tree count towards their own parent directory,
+/// so a subtree with few changes per directory is not rolled up, however many changes it has in total
+/// (see the storm threshold for that).
+///
+/// The rolled up event is placed where the first event of its directory was.
+pub(crate) fn aggregate(events: Vec<EventType>, threshold: usize) -> Vec<EventType> {
+    let mut groups: HashMap<PathBuf, Group> = HashMap::new();
+
+    for event in &events {
+        if let Some(dir) = parent_dir(event) {
+            let group = groups.entry(dir.to_path_buf()).or_default();
+
+            group.count += 1;
+            group.kinds.insert(event.kind());
+        }
+    }
+
+    // groups are taken out once their rolled up event is emitted
+    let mut groups: HashMap<PathBuf, Option<Group>> = groups
+        .into_iter()
+        .filter(|(_, group)| group.count > threshold)
+        .map(|(dir, group)| (dir, Some(group)))
+        .collect();
+
+    if groups.is_empty() {
+        return events;
+    }
+
+    let mut aggregated = Vec::with_capacity(events.len());
+
+    for event in events {
+        let group = match parent_dir(&event).and_then(|dir| groups.get_mut(dir)) {
+            Some(group) => group.take(),
+            None => {
+                aggregated.push(event);
+                continue;
+            }
+        };
+
+        if let Some(group) = group {
+            let dir = parent_dir(&event)
+                .expect("grouped event must have a parent dir")
+                .to_path_buf();
+            let kinds = group.kinds.iter().map(|kind| kind.to_string()).collect();
+
+            aggregated.push(EventType::DirectoryChanged(DirectoryChangedEvent::new(
+                dir,
+                group.count,
+                kinds,
+            )));
+        }
+    }
+
+    aggregated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::events::base::ObjectType;
+    use crate::events::create::CreateEvent;
+    use crate::events::delete::DeleteEvent;
+    use crate::events::rename::RenameEvent;
+
+    fn create(path: &str) -> EventType {
+        EventType::Create(CreateEvent::new(PathBuf::from(path), ObjectType::File))
+    }
+
+    fn delete(path: &str) -> EventType {
+        EventType::Delete(DeleteEvent::new(PathBuf::from(path), ObjectType::File))
+    }
+
+    fn rename(old_path: &str, new_path: &str) -> EventType {
+        EventType::Rename(RenameEvent::new(
+            PathBuf::from(old_path),
+            PathBuf::from(new_path),
+            false,
+            Vec::new(),
+        ))
+    }
+
+    #[test]
+    fn test_below_threshold() {
+        let events = aggregate(vec![create("/img/a.png"), create("/img/b.png")], 2);
+
+        assert_eq!(events.len(), 2);
+    }
+
+    #[test]
+    fn test_counts_direct_children_only() {
+        let events = aggregate(
+            vec![
+                create("/src/a/mod.rs"),
+                create("/src/b/mod.rs"),
+                create("/src/c/mod.rs"),
+                create("/src/d/mod.rs"),
+            ],
+            2,
+        );
+
+        // /src has four changed descendants, but none of its direct children changed
+        assert_eq!(events.len(), 4);
+    }
+
+    #[test]
+    fn test_rolls_up_directory() {
+        let events = aggregate(
+            vec![
+                create("/src/main.rs"),
+                create("/img/a.png"),
+                delete("/img/b.png"),
+                rename("/img/c.png", "/img/d.png"),
+                create("/src/lib.rs"),
+            ],
+            2,
+        );
+
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].kind(), "create");
+        assert_eq!(events[2].kind(), "create");
+
+        match &events[1] {
+            EventType::DirectoryChanged(e) => {
+                assert_eq!(e.path, PathBuf::from("/img"));
+                assert_eq!(e.count, 3);
+                assert_eq!(e.kinds, vec!["create", "delete", "rename"]);
+            }
+            other => panic!("expected a directory changed event, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_keeps_renames_across_directories() {
+        let events = aggregate(
+            vec![
+                create("/img/a.png"),
+                create("/img/b.png"),
+                rename("/img/c.png", "/archive/c.png"),
+            ],
+            1,
+        );
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].kind(), "rename");
+    }
+}
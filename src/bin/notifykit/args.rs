@@ -0,0 +1,369 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use _notifykit_lib::{
+    BatchReceiver, DeliveryMode, DeliveryOptions, EventFilter, Pipeline, WatchError, Watcher, WatcherOptions,
+};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::supervisor::Supervisor;
+#[cfg(unix)]
+use crate::supervisor::{Signal, parse_signal};
+
+/// Watch filesystem changes
+#[derive(Debug, Parser)]
+#[command(name = "notifykit", version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run a command whenever the watched paths change
+    Run(RunArgs),
+    /// Print every batch of events of the watched paths, e.g. to attach to bug reports
+    Watch(PrintArgs),
+    /// Print the batches a recording of backend events (see `watch --record`) is delivered in
+    Replay(ReplayArgs),
+}
+
+/// How events are batched and filtered, the same as in the Python `Notifier`
+#[derive(Debug, Args)]
+pub struct DeliveryArgs {
+    /// How long events are buffered before delivery, in milliseconds
+    #[arg(long = "debounce", value_name = "MS", default_value_t = 200)]
+    pub debounce_ms: u64,
+
+    /// How often buffered events are checked for delivery, in milliseconds
+    #[arg(long = "tick", value_name = "MS", default_value_t = 50, value_parser = clap::value_parser!(u64).range(1..))]
+    pub tick_ms: u64,
+
+    /// How buffered events are released for delivery: delay, debounce, leading or throttle
+    #[arg(long, default_value = "delay")]
+    pub mode: DeliveryMode,
+
+    /// The longest time events can be held back in the debounce mode, in milliseconds
+    #[arg(long = "max-wait", value_name = "MS")]
+    pub max_wait_ms: Option<u64>,
+
+    /// Ignore events under directories with this name (can be repeated)
+    #[arg(long = "ignore-dir", value_name = "NAME")]
+    pub ignore_dirs: Vec<String>,
+
+    /// Ignore events of paths matching this regular expression (can be repeated)
+    #[arg(long = "ignore-pattern", value_name = "REGEX")]
+    pub ignore_patterns: Vec<String>,
+
+    /// Ignore events under this path (can be repeated)
+    #[arg(long = "ignore-path", value_name = "PATH")]
+    pub ignore_paths: Vec<String>,
+
+    /// Reduce events of each path in a batch to a canonical summary
+    #[arg(long)]
+    pub collapse: bool,
+}
+
+impl DeliveryArgs {
+    pub fn options(&self) -> DeliveryOptions {
+        DeliveryOptions {
+            mode: self.mode,
+            buffering_time: Duration::from_millis(self.debounce_ms),
+            max_wait: self.max_wait_ms.map(Duration::from_millis),
+        }
+    }
+
+    pub fn tick(&self) -> Duration {
+        Duration::from_millis(self.tick_ms)
+    }
+
+    pub fn pipeline(&self) -> Result<Pipeline, WatchError> {
+        let mut pipeline = Pipeline::default();
+        pipeline.collapse = self.collapse;
+
+        if !self.ignore_dirs.is_empty() || !self.ignore_patterns.is_empty() || !self.ignore_paths.is_empty() {
+            pipeline.event_filter = Some(EventFilter::new(
+                self.ignore_dirs.clone(),
+                self.ignore_patterns.clone(),
+                self.ignore_paths.clone(),
+            )?);
+        }
+
+        Ok(pipeline)
+    }
+}
+
+/// How paths are watched and their events delivered
+#[derive(Debug, Args)]
+pub struct WatchArgs {
+    #[command(flatten)]
+    pub delivery: DeliveryArgs,
+
+    /// Don't follow symlinks
+    #[arg(long)]
+    pub no_follow_symlinks: bool,
+
+    /// Watch the given directories only, without their subdirectories
+    #[arg(long)]
+    pub non_recursive: bool,
+}
+
+impl WatchArgs {
+    /// Start watching the paths and delivering their events.
+    pub fn watch(&self, paths: &[PathBuf]) -> Result<(Watcher, BatchReceiver), WatchError> {
+        let mut watcher = Watcher::new(WatcherOptions {
+            delivery: self.delivery.options(),
+            follow_symlinks: !self.no_follow_symlinks,
+            ..WatcherOptions::default()
+        })?;
+
+        watcher.watch(paths, !self.non_recursive, false)?;
+
+        let rx = watcher.subscribe();
+        watcher.start_drain(self.delivery.tick(), self.delivery.pipeline()?);
+
+        Ok((watcher, rx))
+    }
+}
+
+/// What to do with changes that happen while the command is still running
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum OnBusy {
+    /// Run the command again once it exits, with all changes made in the meantime
+    Queue,
+    /// Stop the command (see --signal and --grace-period) and start it again
+    Restart,
+    /// Skip the changes
+    Ignore,
+}
+
+#[derive(Debug, Args)]
+pub struct RunArgs {
+    /// Paths to watch (can be repeated)
+    #[arg(short = 'w', long = "watch", value_name = "PATH", default_value = ".")]
+    pub paths: Vec<PathBuf>,
+
+    #[command(flatten)]
+    pub watch: WatchArgs,
+
+    /// What to do with changes that happen while the command is still running
+    #[arg(long, value_enum, default_value_t = OnBusy::Queue)]
+    pub on_busy: OnBusy,
+
+    /// Stop and restart the command on changes, for long-running processes like servers (same as --on-busy=restart)
+    #[arg(long, conflicts_with = "on_busy")]
+    pub restart: bool,
+
+    /// The signal that asks the command to exit on restarts (e.g. SIGTERM, SIGHUP, SIGUSR1)
+    #[cfg(unix)]
+    #[arg(long, value_name = "SIGNAL", default_value = "SIGTERM", value_parser = parse_signal)]
+    pub signal: Signal,
+
+    /// How long the command has to exit after the signal before it's killed, in milliseconds
+    #[arg(long = "grace-period", value_name = "MS", default_value_t = 5000)]
+    pub grace_period_ms: u64,
+
+    /// Clear the screen before every run
+    #[arg(long)]
+    pub clear: bool,
+
+    /// Don't run the command until the first change
+    #[arg(long)]
+    pub postpone: bool,
+
+    /// The command to run, as is when given several arguments or with the shell when given one (e.g. 'make && ./app')
+    #[arg(last = true, required = true, value_name = "COMMAND")]
+    pub command: Vec<String>,
+}
+
+impl RunArgs {
+    pub fn on_busy(&self) -> OnBusy {
+        if self.restart { OnBusy::Restart } else { self.on_busy }
+    }
+
+    pub fn command(&self) -> Vec<String> {
+        self.command.clone()
+    }
+
+    pub fn supervisor(&self) -> Supervisor {
+        let grace_period = Duration::from_millis(self.grace_period_ms);
+
+        #[cfg(unix)]
+        return Supervisor::new(self.signal, grace_period);
+
+        #[cfg(not(unix))]
+        return Supervisor::new(grace_period);
+    }
+}
+
+/// How events are printed
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// A human-readable line per batch and per event
+    Text,
+    /// A single JSON array of batches, closed once notifykit exits
+    Json,
+    /// A JSON object per line for every batch
+    Ndjson,
+}
+
+#[derive(Debug, Args)]
+pub struct PrintArgs {
+    /// Paths to watch
+    #[arg(value_name = "PATH", default_value = ".")]
+    pub paths: Vec<PathBuf>,
+
+    #[command(flatten)]
+    pub watch: WatchArgs,
+
+    /// How events are printed
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    pub format: Format,
+
+    /// Print the events of the backend as well, as they come before debouncing and filtering
+    #[arg(long)]
+    pub raw: bool,
+
+    /// Record the events of the backend to the file, to replay them later
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct ReplayArgs {
+    /// The recording made with `watch --record`
+    #[arg(value_name = "FILE")]
+    pub recording: PathBuf,
+
+    #[command(flatten)]
+    pub delivery: DeliveryArgs,
+
+    /// How events are printed
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    pub format: Format,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_args(args: &[&str]) -> RunArgs {
+        match Cli::try_parse_from(args).unwrap().command {
+            Command::Run(args) => args,
+            command => panic!("unexpected command: {command:?}"),
+        }
+    }
+
+    fn print_args(args: &[&str]) -> PrintArgs {
+        match Cli::try_parse_from(args).unwrap().command {
+            Command::Watch(args) => args,
+            command => panic!("unexpected command: {command:?}"),
+        }
+    }
+
+    #[test]
+    fn test_run_args() {
+        let args = run_args(&[
+            "notifykit",
+            "run",
+            "-w",
+            "src",
+            "--ignore-dir",
+            "target",
+            "--mode",
+            "debounce",
+            "--",
+            "cargo",
+            "test",
+        ]);
+
+        assert_eq!(args.paths, vec![PathBuf::from("src")]);
+        assert_eq!(args.watch.delivery.ignore_dirs, vec!["target".to_string()]);
+        assert_eq!(args.watch.delivery.mode, DeliveryMode::Debounce);
+        assert_eq!(args.on_busy(), OnBusy::Queue);
+        assert_eq!(args.command(), vec!["cargo", "test"]);
+    }
+
+    #[test]
+    fn test_restart_is_on_busy_restart() {
+        let args = run_args(&["notifykit", "run", "--restart", "--", "./server"]);
+
+        assert_eq!(args.paths, vec![PathBuf::from(".")]);
+        assert_eq!(args.on_busy(), OnBusy::Restart);
+        assert!(Cli::try_parse_from(["notifykit", "run", "--restart", "--on-busy", "ignore", "--", "ls"]).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stop_signal() {
+        assert_eq!(run_args(&["notifykit", "run", "--", "ls"]).signal, Signal::SIGTERM);
+        assert_eq!(
+            run_args(&["notifykit", "run", "--signal", "hup", "--", "ls"]).signal,
+            Signal::SIGHUP
+        );
+        assert!(Cli::try_parse_from(["notifykit", "run", "--signal", "SIGNOPE", "--", "ls"]).is_err());
+    }
+
+    #[test]
+    fn test_print_args() {
+        let args = print_args(&[
+            "notifykit",
+            "watch",
+            "src",
+            "tests",
+            "--format=ndjson",
+            "--raw",
+            "--record",
+            "events.rec",
+            "--non-recursive",
+        ]);
+
+        assert_eq!(args.paths, vec![PathBuf::from("src"), PathBuf::from("tests")]);
+        assert_eq!(args.format, Format::Ndjson);
+        assert!(args.raw);
+        assert_eq!(args.record, Some(PathBuf::from("events.rec")));
+        assert!(args.watch.non_recursive);
+
+        let args = print_args(&["notifykit", "watch"]);
+
+        assert_eq!(args.paths, vec![PathBuf::from(".")]);
+        assert_eq!(args.format, Format::Text);
+        assert!(!args.raw);
+        assert_eq!(args.record, None);
+    }
+
+    #[test]
+    fn test_replay_args() {
+        let args = match Cli::try_parse_from(["notifykit", "replay", "events.rec", "--mode", "debounce"])
+            .unwrap()
+            .command
+        {
+            Command::Replay(args) => args,
+            command => panic!("unexpected command: {command:?}"),
+        };
+
+        assert_eq!(args.recording, PathBuf::from("events.rec"));
+        assert_eq!(args.delivery.options().mode, DeliveryMode::Debounce);
+        assert_eq!(args.delivery.tick(), Duration::from_millis(50));
+        assert!(Cli::try_parse_from(["notifykit", "replay", "events.rec", "--non-recursive"]).is_err());
+    }
+
+    #[test]
+    fn test_tick_must_be_positive() {
+        assert_eq!(
+            run_args(&["notifykit", "run", "--tick", "10", "--", "ls"])
+                .watch
+                .delivery
+                .tick_ms,
+            10
+        );
+        assert!(Cli::try_parse_from(["notifykit", "run", "--tick", "0", "--", "ls"]).is_err());
+        assert!(Cli::try_parse_from(["notifykit", "watch", "--tick", "0"]).is_err());
+        assert!(Cli::try_parse_from(["notifykit", "replay", "events.rec", "--tick", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_command_is_required() {
+        assert!(Cli::try_parse_from(["notifykit", "run", "-w", "src"]).is_err());
+    }
+}
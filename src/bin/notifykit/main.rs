@@ -0,0 +1,31 @@
+//! The `notifykit` command line tool, watching paths the same way as the Python package does.
+
+mod args;
+mod print;
+mod run;
+mod supervisor;
+
+use std::process::ExitCode;
+
+use clap::Parser;
+
+use crate::args::{Cli, Command};
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Command::Run(args) => run::run(args).await,
+        Command::Watch(args) => print::print(args).await,
+        Command::Replay(args) => print::replay(args),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("notifykit: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
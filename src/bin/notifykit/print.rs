@@ -0,0 +1,270 @@
+use std::error::Error;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use _notifykit_lib::{EventType, RecvError, Replay, read_recording};
+use serde_json::{Value, json};
+
+use crate::args::{Format, PrintArgs, ReplayArgs};
+use crate::supervisor::{Signals, is_stop_signal};
+
+/// Something that happened to the watcher, printed as it comes
+#[derive(Debug)]
+enum Record<'a> {
+    /// A batch of events as delivered to consumers
+    Batch(&'a [EventType]),
+    /// An event of the backend before it's processed
+    Raw(&'a notify::Event),
+    /// The given number of batches were dropped because they weren't printed fast enough
+    Lagged(u64),
+}
+
+struct Printer<W> {
+    out: W,
+    format: Format,
+    /// How many records are printed so far
+    printed: usize,
+}
+
+impl<W: Write> Printer<W> {
+    fn new(out: W, format: Format) -> Self {
+        Self {
+            out,
+            format,
+            printed: 0,
+        }
+    }
+
+    fn print(&mut self, elapsed: Duration, record: Record) -> io::Result<()> {
+        match self.format {
+            Format::Text => self.print_text(elapsed, record)?,
+            Format::Json => {
+                let separator = if self.printed == 0 { "[\n" } else { ",\n" };
+                write!(self.out, "{separator}{}", to_json(elapsed, record))?;
+            }
+            Format::Ndjson => writeln!(self.out, "{}", to_json(elapsed, record))?,
+        }
+
+        self.printed += 1;
+
+        // records are printed as they come, even when the output isn't a terminal
+        self.out.flush()
+    }
+
+    fn print_text(&mut self, elapsed: Duration, record: Record) -> io::Result<()> {
+        let time = format!("[{:>10.3}s]", elapsed.as_secs_f64());
+
+        match record {
+            Record::Batch(batch) => {
+                writeln!(self.out, "{time} batch of {} event(s)", batch.len())?;
+
+                for event in batch {
+                    writeln!(self.out, "    {}", event_to_text(event))?;
+                }
+            }
+            Record::Raw(event) => {
+                let paths = event.paths.iter().map(|path| path.display().to_string());
+
+                writeln!(
+                    self.out,
+                    "{time} raw {:?} {}",
+                    event.kind,
+                    paths.collect::<Vec<_>>().join(" ")
+                )?;
+            }
+            Record::Lagged(skipped) => writeln!(self.out, "{time} lagged behind, {skipped} batch(es) dropped")?,
+        }
+
+        Ok(())
+    }
+
+    /// Close the JSON array, so the output is a valid JSON document.
+    fn finish(&mut self) -> io::Result<()> {
+        if self.format == Format::Json {
+            let opening = if self.printed == 0 { "[" } else { "" };
+            writeln!(self.out, "{opening}\n]")?;
+        }
+
+        self.out.flush()
+    }
+}
+
+fn to_json(elapsed: Duration, record: Record) -> Value {
+    let elapsed_ms = elapsed.as_millis() as u64;
+
+    match record {
+        Record::Batch(batch) => json!({"elapsed_ms": elapsed_ms, "events": batch}),
+        Record::Raw(event) => json!({"elapsed_ms": elapsed_ms, "raw": event}),
+        Record::Lagged(skipped) => json!({"elapsed_ms": elapsed_ms, "lagged": skipped}),
+    }
+}
+
+/// The kind and paths of the event followed by the rest of its fields in JSON (e.g. `create /a.txt {"file_type":"FILE"}`)
+fn event_to_text(event: &EventType) -> String {
+    let paths = match event {
+        EventType::Rename(e) => format!("{} -> {}", e.old_path.display(), e.new_path.display()),
+        _ => event.path().map(|path| path.display().to_string()).unwrap_or_default(),
+    };
+
+    let mut text = format!("{} {paths}", event.kind());
+
+    if let Ok(Value::Object(mut fields)) = serde_json::to_value(event) {
+        for name in ["kind", "path", "old_path", "new_path"] {
+            fields.remove(name);
+        }
+
+        if !fields.is_empty() {
+            text.push(' ');
+            text.push_str(&Value::Object(fields).to_string());
+        }
+    }
+
+    text
+}
+
+/// Print events of the watched paths until interrupted.
+pub async fn print(args: PrintArgs) -> Result<(), Box<dyn Error>> {
+    let started = Instant::now();
+    let (mut watcher, mut rx) = args.watch.watch(&args.paths)?;
+
+    let mut raw = match args.raw {
+        true => Some(watcher.backend_events()?),
+        false => None,
+    };
+
+    if let Some(ref recording) = args.record {
+        watcher.record(recording)?;
+    }
+
+    let mut printer = Printer::new(io::stdout().lock(), args.format);
+    let mut signals = Signals::new()?;
+
+    let result = loop {
+        let printed = tokio::select! {
+            batch = rx.recv() => match batch {
+                Ok(batch) => printer.print(started.elapsed(), Record::Batch(&batch)),
+                Err(RecvError::Lagged(skipped)) => printer.print(started.elapsed(), Record::Lagged(skipped)),
+                Err(RecvError::Closed) => break Ok(()),
+            },
+            Some(event) = async {
+                match raw {
+                    Some(ref mut raw) => raw.recv().await,
+                    None => std::future::pending().await,
+                }
+            } => printer.print(started.elapsed(), Record::Raw(&event)),
+            signal = signals.recv() => {
+                if is_stop_signal(signal) {
+                    break Ok(());
+                }
+
+                Ok(())
+            }
+        };
+
+        if let Err(e) = printed {
+            break Err(e);
+        }
+    };
+
+    watcher.close();
+
+    match result.and_then(|()| printer.finish()) {
+        // the reader has gone away (e.g. `notifykit watch | head`), so there's nobody to report to
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+/// Print the batches the recording is delivered in, with the time of the recording they're delivered at.
+pub fn replay(args: ReplayArgs) -> Result<(), Box<dyn Error>> {
+    let events = read_recording(&args.recording)?;
+    let replay = Replay::new(args.delivery.tick(), args.delivery.pipeline()?);
+
+    let mut printer = Printer::new(io::stdout().lock(), args.format);
+
+    let printed = replay
+        .run(&args.delivery.options(), &events)
+        .iter()
+        .try_for_each(|batch| printer.print(batch.elapsed, Record::Batch(&batch.events)))
+        .and_then(|()| printer.finish());
+
+    match printed {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        printed => Ok(printed?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use _notifykit_lib::events::base::ObjectType;
+    use _notifykit_lib::events::create::CreateEvent;
+    use _notifykit_lib::events::rename::RenameEvent;
+    use notify::event::{CreateKind, EventKind};
+
+    fn printed(format: Format) -> String {
+        let batch: Vec<EventType> = vec![
+            CreateEvent::new("/a".into(), ObjectType::File).into(),
+            RenameEvent::new("/b".into(), "/c".into(), false, vec![]).into(),
+        ];
+        let raw = notify::Event::new(EventKind::Create(CreateKind::File)).add_path("/a".into());
+
+        let mut printer = Printer::new(Vec::new(), format);
+        printer.print(Duration::from_millis(5), Record::Raw(&raw)).unwrap();
+        printer
+            .print(Duration::from_millis(250), Record::Batch(&batch))
+            .unwrap();
+        printer.print(Duration::from_millis(300), Record::Lagged(2)).unwrap();
+        printer.finish().unwrap();
+
+        String::from_utf8(printer.out).unwrap()
+    }
+
+    #[test]
+    fn test_print_text() {
+        assert_eq!(
+            printed(Format::Text),
+            concat!(
+                "[     0.005s] raw Create(File) /a\n",
+                "[     0.250s] batch of 2 event(s)\n",
+                "    create /a {\"file_type\":\"FILE\"}\n",
+                "    rename /b -> /c {\"children\":[],\"is_dir\":false}\n",
+                "[     0.300s] lagged behind, 2 batch(es) dropped\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_print_json() {
+        let records: Value = serde_json::from_str(&printed(Format::Json)).unwrap();
+
+        assert_eq!(records[0]["elapsed_ms"], 5);
+        assert_eq!(records[0]["raw"]["paths"], json!(["/a"]));
+        assert_eq!(
+            records[1]["events"][0],
+            json!({"kind": "create", "path": "/a", "file_type": "FILE"})
+        );
+        assert_eq!(records[1]["events"][1]["kind"], "rename");
+        assert_eq!(records[2], json!({"elapsed_ms": 300, "lagged": 2}));
+
+        let mut printer = Printer::new(Vec::new(), Format::Json);
+        printer.finish().unwrap();
+
+        let records: Value = serde_json::from_slice(&printer.out).unwrap();
+        assert_eq!(records, json!([]));
+    }
+
+    #[test]
+    fn test_print_ndjson() {
+        let printed = printed(Format::Ndjson);
+        let lines: Vec<Value> = printed
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1]["elapsed_ms"], 250);
+        assert_eq!(lines[1]["events"].as_array().unwrap().len(), 2);
+    }
+}
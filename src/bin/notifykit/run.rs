@@ -0,0 +1,272 @@
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use _notifykit_lib::{EventType, RecvError};
+use tokio::process::Command;
+
+use crate::args::{OnBusy, RunArgs};
+use crate::supervisor::{Signals, Supervisor, is_stop_signal};
+
+#[cfg(unix)]
+const SHELL: (&str, &str) = ("sh", "-c");
+#[cfg(windows)]
+const SHELL: (&str, &str) = ("cmd", "/C");
+
+/// Paths changed since the previous run by the kind of change, passed to the command as environment variables
+#[derive(Debug, Default)]
+struct Changes {
+    created: BTreeSet<PathBuf>,
+    modified: BTreeSet<PathBuf>,
+    removed: BTreeSet<PathBuf>,
+    /// Old and new paths of renamed files
+    renamed: BTreeSet<PathBuf>,
+    /// All paths above along with paths of events that don't change files (like access events)
+    changed: BTreeSet<PathBuf>,
+}
+
+impl Changes {
+    fn from_batch(batch: &[EventType]) -> Self {
+        let mut changes = Self::default();
+
+        for event in batch {
+            let paths = match event {
+                EventType::Rename(e) => vec![&e.old_path, &e.new_path],
+                EventType::Access(e) => vec![&e.path],
+                EventType::Create(e) => vec![&e.path],
+                EventType::Delete(e) => vec![&e.path],
+                EventType::ModifyMetadata(e) => vec![&e.path],
+                EventType::ModifyData(e) => vec![&e.path],
+                EventType::ModifyUnknown(e) => vec![&e.path],
+                EventType::ModifyOther(e) => vec![&e.path],
+                EventType::MovedIn(e) => vec![&e.path],
+                EventType::MovedOut(e) => vec![&e.path],
+                EventType::Rescan(e) => vec![&e.path],
+                EventType::DirectoryChanged(e) => vec![&e.path],
+            };
+
+            let kind = match event {
+                EventType::Create(_) | EventType::MovedIn(_) => Some(&mut changes.created),
+                EventType::Delete(_) | EventType::MovedOut(_) => Some(&mut changes.removed),
+                EventType::ModifyMetadata(_)
+                | EventType::ModifyData(_)
+                | EventType::ModifyUnknown(_)
+                | EventType::ModifyOther(_) => Some(&mut changes.modified),
+                EventType::Rename(_) => Some(&mut changes.renamed),
+                _ => None,
+            };
+
+            if let Some(kind) = kind {
+                kind.extend(paths.iter().map(|path| path.to_path_buf()));
+            }
+
+            changes.changed.extend(paths.into_iter().cloned());
+        }
+
+        changes
+    }
+
+    fn merge(&mut self, other: Changes) {
+        self.created.extend(other.created);
+        self.modified.extend(other.modified);
+        self.removed.extend(other.removed);
+        self.renamed.extend(other.renamed);
+        self.changed.extend(other.changed);
+    }
+
+    /// Paths are separated by newlines, as any other separator may be a part of a path.
+    fn env(&self) -> [(&'static str, String); 5] {
+        let join = |paths: &BTreeSet<PathBuf>| {
+            paths
+                .iter()
+                .map(|path| path.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        [
+            ("NOTIFYKIT_CHANGED_PATHS", join(&self.changed)),
+            ("NOTIFYKIT_CREATED_PATHS", join(&self.created)),
+            ("NOTIFYKIT_MODIFIED_PATHS", join(&self.modified)),
+            ("NOTIFYKIT_REMOVED_PATHS", join(&self.removed)),
+            ("NOTIFYKIT_RENAMED_PATHS", join(&self.renamed)),
+        ]
+    }
+}
+
+struct Runner {
+    /// Run with the shell when it's a single argument, so it can be a whole command line
+    command: Vec<String>,
+    clear: bool,
+}
+
+impl Runner {
+    fn spawn(&self, supervisor: &mut Supervisor, changes: &Changes) -> io::Result<()> {
+        if self.clear {
+            // clear the screen along with the scrollback and move the cursor home
+            print!("\x1b[2J\x1b[3J\x1b[H");
+            io::stdout().flush()?;
+        }
+
+        let mut command = self.command();
+        command.envs(changes.env());
+
+        supervisor.spawn(command)
+    }
+
+    /// Arguments are passed as they are, as joining them into a shell command line would lose their quoting.
+    fn command(&self) -> Command {
+        match self.command.as_slice() {
+            [line] => {
+                let mut command = Command::new(SHELL.0);
+                command.arg(SHELL.1).arg(line);
+
+                command
+            }
+            [program, args @ ..] => {
+                let mut command = Command::new(program);
+                command.args(args);
+
+                command
+            }
+            [] => unreachable!("the command is a required argument"),
+        }
+    }
+}
+
+/// Run the command on every batch of changes until interrupted.
+pub async fn run(args: RunArgs) -> Result<(), Box<dyn Error>> {
+    let (mut watcher, mut rx) = args.watch.watch(&args.paths)?;
+
+    let on_busy = args.on_busy();
+    let runner = Runner {
+        command: args.command(),
+        clear: args.clear,
+    };
+
+    let mut supervisor = args.supervisor();
+    let mut signals = Signals::new()?;
+
+    if !args.postpone {
+        runner.spawn(&mut supervisor, &Changes::default())?;
+    }
+
+    // changes made while the command was running in the queue mode
+    let mut queued: Option<Changes> = None;
+
+    loop {
+        tokio::select! {
+            batch = rx.recv() => {
+                let changes = match batch {
+                    Ok(batch) => Changes::from_batch(&batch),
+                    // the dropped batches had changes too, the command just isn't told what they were
+                    Err(RecvError::Lagged(skipped)) => {
+                        eprintln!("notifykit: lagged behind, {skipped} batch(es) dropped");
+                        Changes::default()
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                if !supervisor.is_running() {
+                    runner.spawn(&mut supervisor, &changes)?;
+                    continue;
+                }
+
+                match on_busy {
+                    OnBusy::Queue => queued.get_or_insert_with(Changes::default).merge(changes),
+                    OnBusy::Restart => {
+                        supervisor.stop().await;
+                        runner.spawn(&mut supervisor, &changes)?;
+                    }
+                    OnBusy::Ignore => {}
+                }
+            }
+            status = supervisor.wait() => {
+                match status {
+                    Ok(status) if !status.success() => eprintln!("notifykit: command exited with {status}"),
+                    Ok(_) => {}
+                    Err(e) => eprintln!("notifykit: failed to wait for the command: {e}"),
+                }
+
+                if let Some(changes) = queued.take() {
+                    runner.spawn(&mut supervisor, &changes)?;
+                }
+            }
+            signal = signals.recv() => {
+                if is_stop_signal(signal) {
+                    supervisor.stop_with(signal).await;
+                    break;
+                }
+
+                #[cfg(unix)]
+                supervisor.signal(signal);
+            }
+        }
+    }
+
+    supervisor.stop().await;
+    watcher.close();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use _notifykit_lib::events::base::ObjectType;
+    use _notifykit_lib::events::create::CreateEvent;
+    use _notifykit_lib::events::modify::{DataType, ModifyDataEvent};
+    use _notifykit_lib::events::rename::RenameEvent;
+
+    fn env(changes: &Changes, name: &str) -> String {
+        changes.env().into_iter().find(|(key, _)| *key == name).unwrap().1
+    }
+
+    fn argv(runner: &Runner) -> Vec<String> {
+        let command = runner.command();
+        let command = command.as_std();
+
+        std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_runner_command() {
+        let runner = |command: &[&str]| Runner {
+            command: command.iter().map(|arg| arg.to_string()).collect(),
+            clear: false,
+        };
+
+        assert_eq!(argv(&runner(&["make && ./app"])), vec!["sh", "-c", "make && ./app"]);
+        assert_eq!(
+            argv(&runner(&["grep", "-r", "two words", "src"])),
+            vec!["grep", "-r", "two words", "src"]
+        );
+    }
+
+    #[test]
+    fn test_changes_env() {
+        let mut changes = Changes::from_batch(&[
+            CreateEvent::new("/a".into(), ObjectType::File).into(),
+            ModifyDataEvent::new("/a".into(), DataType::Content).into(),
+        ]);
+        changes.merge(Changes::from_batch(&[RenameEvent::new(
+            "/b".into(),
+            "/c".into(),
+            false,
+            vec![],
+        )
+        .into()]));
+
+        assert_eq!(env(&changes, "NOTIFYKIT_CHANGED_PATHS"), "/a\n/b\n/c");
+        assert_eq!(env(&changes, "NOTIFYKIT_CREATED_PATHS"), "/a");
+        assert_eq!(env(&changes, "NOTIFYKIT_MODIFIED_PATHS"), "/a");
+        assert_eq!(env(&changes, "NOTIFYKIT_RENAMED_PATHS"), "/b\n/c");
+        assert_eq!(env(&changes, "NOTIFYKIT_REMOVED_PATHS"), "");
+    }
+}
@@ -0,0 +1,349 @@
+use std::io;
+use std::process::ExitStatus;
+use std::time::Duration;
+
+#[cfg(unix)]
+pub use nix::sys::signal::Signal;
+#[cfg(unix)]
+use nix::unistd::Pid;
+use tokio::process::{Child, Command};
+
+/// How often `Supervisor::stop()` checks whether the command's process group has exited
+#[cfg(unix)]
+const GROUP_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Parse a signal name like `SIGTERM` or `term`.
+#[cfg(unix)]
+pub fn parse_signal(name: &str) -> Result<Signal, String> {
+    let upper = name.to_uppercase();
+    let full_name = match upper.starts_with("SIG") {
+        true => upper,
+        false => format!("SIG{upper}"),
+    };
+
+    full_name.parse().map_err(|_| format!("unknown signal: {name}"))
+}
+
+/// Runs the command in its own process group, so stopping it stops everything it has spawned as well.
+pub struct Supervisor {
+    child: Option<Child>,
+    /// The ID of the command's process group (the same as the command's process ID)
+    #[cfg(unix)]
+    pgid: Option<Pid>,
+    /// The signal that asks the command to exit
+    #[cfg(unix)]
+    stop_signal: Signal,
+    /// How long the command has to exit after the stop signal before it's killed
+    grace_period: Duration,
+}
+
+impl Supervisor {
+    #[cfg(unix)]
+    pub fn new(stop_signal: Signal, grace_period: Duration) -> Self {
+        Self {
+            child: None,
+            pgid: None,
+            stop_signal,
+            grace_period,
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn new(grace_period: Duration) -> Self {
+        Self {
+            child: None,
+            grace_period,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.child.is_some()
+    }
+
+    pub fn spawn(&mut self, mut command: Command) -> io::Result<()> {
+        #[cfg(unix)]
+        command.process_group(0);
+
+        let child = command.kill_on_drop(true).spawn()?;
+
+        #[cfg(unix)]
+        {
+            self.pgid = child.id().map(|id| Pid::from_raw(id as i32));
+        }
+
+        self.child = Some(child);
+
+        Ok(())
+    }
+
+    /// Wait for the command to exit by itself, killing whatever it left running in its process group.
+    ///
+    /// Never returns if the command isn't running.
+    pub async fn wait(&mut self) -> io::Result<ExitStatus> {
+        let status = match self.child {
+            Some(ref mut child) => child.wait().await,
+            None => std::future::pending().await,
+        };
+
+        self.child = None;
+        self.kill_group();
+
+        status
+    }
+
+    /// Send the signal to the command's process group.
+    #[cfg(unix)]
+    pub fn signal(&self, signal: Signal) {
+        if let Some(pgid) = self.pgid {
+            signal_group(pgid, signal);
+        }
+    }
+
+    /// Ask the command to exit with the stop signal and kill its process group
+    /// if it's still running after the grace period.
+    pub async fn stop(&mut self) {
+        #[cfg(unix)]
+        self.stop_with(self.stop_signal).await;
+
+        #[cfg(not(unix))]
+        if let Some(mut child) = self.child.take() {
+            if let Err(e) = child.kill().await {
+                eprintln!("notifykit: failed to kill the command: {e}");
+            }
+        }
+    }
+
+    /// The same as `stop()` with another signal, to pass on the signals that stop notifykit itself.
+    #[cfg(unix)]
+    pub async fn stop_with(&mut self, signal: Signal) {
+        let Some(mut child) = self.child.take() else {
+            return;
+        };
+
+        self.signal(signal);
+
+        // processes spawned by the command may take longer to shut down than the command itself
+        let deadline = tokio::time::Instant::now() + self.grace_period;
+        let exited = tokio::time::timeout_at(deadline, child.wait()).await.is_ok()
+            && tokio::time::timeout_at(deadline, self.wait_for_group()).await.is_ok();
+
+        if !exited {
+            eprintln!(
+                "notifykit: command didn't exit within {:?} after {signal}, killing it",
+                self.grace_period
+            );
+        }
+
+        self.kill_group();
+
+        if let Err(e) = child.wait().await {
+            eprintln!("notifykit: failed to wait for the command: {e}");
+        }
+    }
+
+    /// Wait until no process is left in the command's process group.
+    ///
+    /// Exited processes nobody has reaped yet still count, so the wait may last until the grace period is over.
+    #[cfg(unix)]
+    async fn wait_for_group(&self) {
+        let Some(pgid) = self.pgid else {
+            return;
+        };
+
+        // no signal is sent, only the existence of the group is checked
+        while nix::sys::signal::killpg(pgid, None).is_ok() {
+            tokio::time::sleep(GROUP_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Signals aren't supported on this platform, so the command is killed right away.
+    #[cfg(not(unix))]
+    pub async fn stop_with(&mut self, _signal: ()) {
+        self.stop().await;
+    }
+
+    fn kill_group(&mut self) {
+        #[cfg(unix)]
+        if let Some(pgid) = self.pgid.take() {
+            signal_group(pgid, Signal::SIGKILL);
+        }
+    }
+}
+
+/// Processes of the group may be gone already, which is fine.
+#[cfg(unix)]
+fn signal_group(pgid: Pid, signal: Signal) {
+    match nix::sys::signal::killpg(pgid, signal) {
+        Ok(()) | Err(nix::errno::Errno::ESRCH) => {}
+        Err(e) => eprintln!("notifykit: failed to send {signal} to the command: {e}"),
+    }
+}
+
+/// Whether the signal sent to notifykit stops it, while the rest of the signals are passed on to the command.
+///
+/// The command runs in its own process group, so it doesn't get the signals sent to the terminal's process group
+/// (like SIGINT on Ctrl+C) by itself.
+#[cfg(unix)]
+pub fn is_stop_signal(signal: Signal) -> bool {
+    matches!(signal, Signal::SIGINT | Signal::SIGTERM)
+}
+
+/// Only Ctrl+C is handled on this platform.
+#[cfg(not(unix))]
+pub fn is_stop_signal(_signal: ()) -> bool {
+    true
+}
+
+/// Signals sent to notifykit itself
+#[cfg(unix)]
+pub struct Signals {
+    interrupt: tokio::signal::unix::Signal,
+    terminate: tokio::signal::unix::Signal,
+    hangup: tokio::signal::unix::Signal,
+    user_defined1: tokio::signal::unix::Signal,
+    user_defined2: tokio::signal::unix::Signal,
+}
+
+#[cfg(unix)]
+impl Signals {
+    pub fn new() -> io::Result<Self> {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        Ok(Self {
+            interrupt: signal(SignalKind::interrupt())?,
+            terminate: signal(SignalKind::terminate())?,
+            hangup: signal(SignalKind::hangup())?,
+            user_defined1: signal(SignalKind::user_defined1())?,
+            user_defined2: signal(SignalKind::user_defined2())?,
+        })
+    }
+
+    pub async fn recv(&mut self) -> Signal {
+        tokio::select! {
+            _ = self.interrupt.recv() => Signal::SIGINT,
+            _ = self.terminate.recv() => Signal::SIGTERM,
+            _ = self.hangup.recv() => Signal::SIGHUP,
+            _ = self.user_defined1.recv() => Signal::SIGUSR1,
+            _ = self.user_defined2.recv() => Signal::SIGUSR2,
+        }
+    }
+}
+
+/// Signals aren't supported on this platform, so only Ctrl+C is handled
+#[cfg(not(unix))]
+pub struct Signals;
+
+#[cfg(not(unix))]
+impl Signals {
+    pub fn new() -> io::Result<Self> {
+        Ok(Self)
+    }
+
+    pub async fn recv(&mut self) {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            eprintln!("notifykit: failed to listen for Ctrl+C: {e}");
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    use std::time::Instant;
+
+    #[test]
+    fn test_parse_signal() {
+        assert_eq!(parse_signal("SIGHUP"), Ok(Signal::SIGHUP));
+        assert_eq!(parse_signal("usr1"), Ok(Signal::SIGUSR1));
+        assert!(parse_signal("SIGNOPE").is_err());
+    }
+
+    fn shell(command: &str) -> Command {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+
+        shell
+    }
+
+    /// Zombies count as dead, as nothing may reap the orphans in containers.
+    fn is_alive(pid: u32) -> bool {
+        match std::fs::read_to_string(format!("/proc/{pid}/stat")) {
+            Ok(stat) => !stat.contains(") Z "),
+            Err(_) => nix::sys::signal::kill(Pid::from_raw(pid as i32), None).is_ok(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stop_kills_process_group() {
+        let mut supervisor = Supervisor::new(Signal::SIGTERM, Duration::from_millis(500));
+        let pid_file = std::env::temp_dir().join(format!("notifykit-supervisor-{}", std::process::id()));
+
+        // the grandchild ignores SIGTERM, so it has to be killed along with the group
+        supervisor
+            .spawn(shell(&format!(
+                "sh -c 'trap \"\" TERM; sleep 30' & echo $! > {}; wait",
+                pid_file.display()
+            )))
+            .unwrap();
+
+        let grandchild = loop {
+            if let Ok(Ok(pid)) = std::fs::read_to_string(&pid_file).map(|pid| pid.trim().parse::<u32>()) {
+                break pid;
+            }
+
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+
+        supervisor.stop().await;
+
+        assert!(!supervisor.is_running());
+        // the grandchild is killed asynchronously
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while is_alive(grandchild) && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(!is_alive(grandchild));
+
+        std::fs::remove_file(pid_file).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stop_waits_for_process_group() {
+        let mut supervisor = Supervisor::new(Signal::SIGTERM, Duration::from_secs(2));
+        let done_file = std::env::temp_dir().join(format!("notifykit-supervisor-done-{}", std::process::id()));
+        let _ = std::fs::remove_file(&done_file);
+
+        // the grandchild takes a while to shut down after the command itself has exited
+        supervisor
+            .spawn(shell(&format!(
+                "sh -c 'trap \"sleep 0.3; touch {}; exit\" TERM; sleep 30 & wait' & wait",
+                done_file.display()
+            )))
+            .unwrap();
+        // let the shells set up the trap
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        supervisor.stop().await;
+
+        assert!(done_file.exists());
+
+        std::fs::remove_file(done_file).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stop_escalates_to_sigkill() {
+        let mut supervisor = Supervisor::new(Signal::SIGHUP, Duration::from_millis(100));
+
+        supervisor.spawn(shell("trap '' HUP; sleep 30")).unwrap();
+        // let the shell set up the trap
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let started = Instant::now();
+        supervisor.stop().await;
+
+        assert!(!supervisor.is_running());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
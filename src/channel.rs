@@ -0,0 +1,386 @@
+use std::collections::HashMap;
+use std::mem::{Discriminant, discriminant};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::{broadcast, mpsc};
+
+use crate::events::EventType;
+use crate::stream::{BlockingEvents, EventStream};
+
+/// Defines what happens when a consumer falls behind and the event buffer is full.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum BackpressurePolicy {
+    /// Drop the oldest batches and keep going (the number of dropped batches is still available to the consumer)
+    #[default]
+    DropOldest,
+    /// Make the drain loop wait until the consumer catches up
+    Block,
+    /// Merge pending batches into one batch with duplicate events removed
+    Coalesce,
+    /// Drop the oldest batches and report the number of lost batches to the consumer
+    Raise,
+}
+
+impl FromStr for BackpressurePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "drop_oldest" => Ok(Self::DropOldest),
+            "block" => Ok(Self::Block),
+            "coalesce" => Ok(Self::Coalesce),
+            "raise" => Ok(Self::Raise),
+            _ => Err(format!(
+                "unknown backpressure policy: {s} (expected one of drop_oldest, block, coalesce, raise)"
+            )),
+        }
+    }
+}
+
+/// How often `BatchSender::wait_for_room()` checks whether consumers of a broadcast channel have caught up
+const BROADCAST_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Identifies events that are considered duplicates when batches are coalesced
+type EventKey = (Discriminant<EventType>, PathBuf, Option<PathBuf>);
+
+fn event_key(event: &EventType) -> EventKey {
+    let (path, target_path) = match event {
+        EventType::Rename(e) => (e.old_path.clone(), Some(e.new_path.clone())),
+        _ => (event.path().map(|p| p.to_path_buf()).unwrap_or_default(), None),
+    };
+
+    (discriminant(event), path, target_path)
+}
+
+/// Events pending delivery to a consumer of the `coalesce` channel.
+///
+/// Only the latest occurrence of each event is kept, placed where it happened last,
+/// so the order of the batch still tells what each path ended up as (e.g. create+delete+create is delete+create).
+/// There's no cap on the batch, but it holds one event per kind and path at most,
+/// so it grows with the number of changed paths rather than the number of events while the consumer stalls.
+#[derive(Debug, Default)]
+pub struct CoalescedBatch {
+    /// Events in the order of their latest occurrence, with the earlier ones taken out
+    events: Vec<Option<EventType>>,
+    keys: HashMap<EventKey, usize>,
+}
+
+impl CoalescedBatch {
+    fn merge(&mut self, batch: Vec<EventType>) {
+        for event in batch {
+            if let Some(index) = self.keys.insert(event_key(&event), self.events.len()) {
+                self.events[index] = None;
+            }
+
+            self.events.push(Some(event));
+        }
+
+        // don't let the gaps outgrow the events
+        if self.events.len() > self.keys.len() * 2 {
+            self.compact();
+        }
+    }
+
+    fn compact(&mut self) {
+        self.events.retain(Option::is_some);
+
+        for (index, event) in self.events.iter().enumerate() {
+            if let Some(event) = event {
+                self.keys.insert(event_key(event), index);
+            }
+        }
+    }
+
+    fn take(&mut self) -> Vec<EventType> {
+        self.keys.clear();
+
+        std::mem::take(&mut self.events).into_iter().flatten().collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum BatchSender {
+    /// Along with the capacity of the channel
+    Broadcast(broadcast::Sender<Vec<EventType>>, usize),
+    Bounded(mpsc::Sender<Vec<EventType>>),
+    Coalesce(Arc<Mutex<CoalescedBatch>>, mpsc::Sender<()>),
+}
+
+#[derive(Debug)]
+pub enum BatchReceiver {
+    Broadcast(broadcast::Receiver<Vec<EventType>>, BackpressurePolicy),
+    Bounded(mpsc::Receiver<Vec<EventType>>),
+    Coalesce(Arc<Mutex<CoalescedBatch>>, mpsc::Receiver<()>),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RecvError {
+    /// All senders are gone and no more batches are going to be delivered
+    Closed,
+    /// The consumer fell behind and the given number of batches were dropped
+    Lagged(u64),
+}
+
+/// Create a channel to deliver event batches from the drain loop to consumers
+/// according to the given backpressure policy.
+pub(crate) fn channel(policy: BackpressurePolicy, capacity: usize) -> (BatchSender, BatchReceiver) {
+    match policy {
+        BackpressurePolicy::DropOldest | BackpressurePolicy::Raise => {
+            let (tx, rx) = broadcast::channel(capacity);
+
+            (
+                BatchSender::Broadcast(tx, capacity),
+                BatchReceiver::Broadcast(rx, policy),
+            )
+        }
+        BackpressurePolicy::Block => {
+            let (tx, rx) = mpsc::channel(capacity);
+
+            (BatchSender::Bounded(tx), BatchReceiver::Bounded(rx))
+        }
+        BackpressurePolicy::Coalesce => {
+            let batch = Arc::new(Mutex::new(CoalescedBatch::default()));
+            // only used to wake up the consumer, the batch itself is merged in place
+            let (tx, rx) = mpsc::channel(1);
+
+            (
+                BatchSender::Coalesce(Arc::clone(&batch), tx),
+                BatchReceiver::Coalesce(batch, rx),
+            )
+        }
+    }
+}
+
+impl BatchSender {
+    /// Create one more receiver of the channel.
+    ///
+    /// Only broadcast channels support several receivers, so `None` is returned for the rest.
+    pub fn subscribe(&self, policy: BackpressurePolicy) -> Option<BatchReceiver> {
+        match self {
+            BatchSender::Broadcast(tx, _) => Some(BatchReceiver::Broadcast(tx.subscribe(), policy)),
+            _ => None,
+        }
+    }
+
+    /// Wait until the consumers of a broadcast channel have room for one more batch,
+    /// so the following batch doesn't push out the ones they haven't received yet.
+    ///
+    /// Other channels wait for room (or merge batches) on their own, so they return right away.
+    pub async fn wait_for_room(&self) {
+        if let BatchSender::Broadcast(tx, capacity) = self {
+            // broadcast channels can't notify senders about received batches
+            while tx.len() >= *capacity && tx.receiver_count() > 0 {
+                tokio::time::sleep(BROADCAST_POLL_INTERVAL).await;
+            }
+        }
+    }
+
+    pub async fn send(&self, batch: Vec<EventType>) {
+        match self {
+            BatchSender::Broadcast(tx, _) => {
+                let _ = tx.send(batch);
+            }
+            BatchSender::Bounded(tx) => {
+                let _ = tx.send(batch).await;
+            }
+            BatchSender::Coalesce(pending, tx) => {
+                match pending.lock() {
+                    Ok(mut pending) => pending.merge(batch),
+                    Err(e) => {
+                        eprintln!("notifykit: coalesced batch lock poisoned, dropping event batch: {e}");
+                        return;
+                    }
+                }
+
+                // a full channel means the consumer is already going to be woken up
+                let _ = tx.try_send(());
+            }
+        }
+    }
+}
+
+impl BatchReceiver {
+    /// The backpressure policy the receiver was created with,
+    /// so consumers can tell whether dropped batches have to be reported or just skipped.
+    pub fn backpressure(&self) -> BackpressurePolicy {
+        match self {
+            BatchReceiver::Broadcast(_, policy) => *policy,
+            BatchReceiver::Bounded(_) => BackpressurePolicy::Block,
+            BatchReceiver::Coalesce(..) => BackpressurePolicy::Coalesce,
+        }
+    }
+
+    /// Receive the next batch.
+    ///
+    /// Batches dropped because the consumer fell behind are reported as `RecvError::Lagged`
+    /// by both `drop_oldest` and `raise` channels; the consumer decides whether to skip or to surface them.
+    pub async fn recv(&mut self) -> Result<Vec<EventType>, RecvError> {
+        match self {
+            BatchReceiver::Broadcast(rx, _) => match rx.recv().await {
+                Ok(batch) => Ok(batch),
+                Err(broadcast::error::RecvError::Lagged(n)) => Err(RecvError::Lagged(n)),
+                Err(broadcast::error::RecvError::Closed) => Err(RecvError::Closed),
+            },
+            BatchReceiver::Bounded(rx) => rx.recv().await.ok_or(RecvError::Closed),
+            BatchReceiver::Coalesce(pending, rx) => loop {
+                let signal = rx.recv().await;
+
+                let batch = pending.lock().map(|mut p| p.take()).unwrap_or_default();
+
+                if !batch.is_empty() {
+                    return Ok(batch);
+                }
+
+                if signal.is_none() {
+                    return Err(RecvError::Closed);
+                }
+            },
+        }
+    }
+
+    /// Blocking version of `recv()` for consumers outside of async code.
+    ///
+    /// Panics when called within an async execution context (see `tokio::sync::mpsc::Receiver::blocking_recv`).
+    pub fn blocking_recv(&mut self) -> Result<Vec<EventType>, RecvError> {
+        match self {
+            BatchReceiver::Broadcast(rx, _) => match rx.blocking_recv() {
+                Ok(batch) => Ok(batch),
+                Err(broadcast::error::RecvError::Lagged(n)) => Err(RecvError::Lagged(n)),
+                Err(broadcast::error::RecvError::Closed) => Err(RecvError::Closed),
+            },
+            BatchReceiver::Bounded(rx) => rx.blocking_recv().ok_or(RecvError::Closed),
+            BatchReceiver::Coalesce(pending, rx) => loop {
+                let signal = rx.blocking_recv();
+
+                let batch = pending.lock().map(|mut p| p.take()).unwrap_or_default();
+
+                if !batch.is_empty() {
+                    return Ok(batch);
+                }
+
+                if signal.is_none() {
+                    return Err(RecvError::Closed);
+                }
+            },
+        }
+    }
+
+    /// Turn the receiver into a stream of event batches.
+    pub fn into_stream(self) -> EventStream {
+        EventStream::new(self)
+    }
+
+    /// Turn the receiver into a blocking iterator over event batches.
+    pub fn into_blocking_iter(self) -> BlockingEvents {
+        BlockingEvents::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::events::base::ObjectType;
+    use crate::events::create::CreateEvent;
+    use crate::events::delete::DeleteEvent;
+    use crate::events::modify::{DataType, ModifyDataEvent};
+
+    fn create(path: &str) -> EventType {
+        EventType::Create(CreateEvent::new(PathBuf::from(path), ObjectType::File))
+    }
+
+    fn delete(path: &str) -> EventType {
+        EventType::Delete(DeleteEvent::new(PathBuf::from(path), ObjectType::File))
+    }
+
+    fn modify(path: &str) -> EventType {
+        EventType::ModifyData(ModifyDataEvent::new(PathBuf::from(path), DataType::Content))
+    }
+
+    #[test]
+    fn test_parse_policy() {
+        assert_eq!("block".parse(), Ok(BackpressurePolicy::Block));
+        assert!("unknown".parse::<BackpressurePolicy>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_merges_pending_batches() {
+        let (tx, mut rx) = channel(BackpressurePolicy::Coalesce, 4);
+
+        tx.send(vec![create("/a"), modify("/a")]).await;
+        tx.send(vec![modify("/a"), modify("/b")]).await;
+
+        let batch = rx.recv().await.unwrap();
+
+        assert_eq!(batch.len(), 3);
+        assert!(matches!(batch[0], EventType::Create(_)));
+        assert_eq!(batch[2].path(), Some(PathBuf::from("/b").as_path()));
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_keeps_latest_occurrence() {
+        let (tx, mut rx) = channel(BackpressurePolicy::Coalesce, 4);
+
+        tx.send(vec![create("/a"), modify("/b")]).await;
+        tx.send(vec![delete("/a")]).await;
+        tx.send(vec![create("/a")]).await;
+
+        let batch = rx.recv().await.unwrap();
+
+        // /a exists in the end
+        assert_eq!(
+            batch.iter().map(EventType::kind).collect::<Vec<_>>(),
+            vec!["modify_data", "delete", "create"]
+        );
+    }
+
+    #[test]
+    fn test_coalesce_compacts_pending_batch() {
+        let mut pending = CoalescedBatch::default();
+
+        for _ in 0..100 {
+            pending.merge(vec![modify("/a"), modify("/b")]);
+        }
+
+        assert!(pending.events.len() <= 4);
+        assert_eq!(pending.take().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_closed() {
+        let (tx, mut rx) = channel(BackpressurePolicy::Coalesce, 4);
+
+        tx.send(vec![create("/a")]).await;
+        drop(tx);
+
+        assert_eq!(rx.recv().await.unwrap().len(), 1);
+        assert_eq!(rx.recv().await.unwrap_err(), RecvError::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_raise_reports_lagged_batches() {
+        let (tx, mut rx) = channel(BackpressurePolicy::Raise, 1);
+
+        tx.send(vec![create("/a")]).await;
+        tx.send(vec![create("/b")]).await;
+        tx.send(vec![create("/c")]).await;
+
+        assert_eq!(rx.recv().await.unwrap_err(), RecvError::Lagged(2));
+        assert_eq!(rx.recv().await.unwrap()[0].path(), Some(PathBuf::from("/c").as_path()));
+    }
+
+    #[tokio::test]
+    async fn test_drop_oldest_reports_lagged_batches() {
+        let (tx, mut rx) = channel(BackpressurePolicy::DropOldest, 1);
+
+        tx.send(vec![create("/a")]).await;
+        tx.send(vec![create("/b")]).await;
+
+        assert_eq!(rx.backpressure(), BackpressurePolicy::DropOldest);
+        assert_eq!(rx.recv().await.unwrap_err(), RecvError::Lagged(1));
+        assert_eq!(rx.recv().await.unwrap()[0].path(), Some(PathBuf::from("/b").as_path()));
+    }
+}
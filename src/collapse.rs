@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::events::EventType;
+use crate::events::access::AccessEvent;
+use crate::events::create::CreateEvent;
+use crate::events::delete::DeleteEvent;
+
+/// The canonical summary of what happened to one path within a batch
+#[derive(Debug)]
+enum Summary {
+    Created(CreateEvent),
+    Deleted(DeleteEvent),
+    /// Holds one of the modify events
+    Modified(EventType),
+    Accessed(AccessEvent),
+    /// The path was deleted and then created again
+    Replaced(DeleteEvent, CreateEvent),
+    /// The path was created and deleted within the batch
+    Vanished,
+}
+
+impl Summary {
+    fn new(event: EventType) -> Self {
+        match event {
+            EventType::Create(e) => Summary::Created(e),
+            EventType::Delete(e) => Summary::Deleted(e),
+            EventType::Access(e) => Summary::Accessed(e),
+            modify => Summary::Modified(modify),
+        }
+    }
+
+    fn push(self, event: EventType) -> Self {
+        match (self, event) {
+            // creation covers any changes that follow it
+            (Summary::Created(_), EventType::Delete(_)) => Summary::Vanished,
+            (Summary::Created(c), _) => Summary::Created(c),
+
+            (Summary::Deleted(d), EventType::Create(c)) => Summary::Replaced(d, c),
+            (Summary::Deleted(d), _) => Summary::Deleted(d),
+
+            (Summary::Replaced(_, _), EventType::Delete(d)) => Summary::Deleted(d),
+            (Summary::Replaced(d, c), _) => Summary::Replaced(d, c),
+
+            (Summary::Vanished, EventType::Create(c)) => Summary::Created(c),
+            (Summary::Vanished, _) => Summary::Vanished,
+
+            (Summary::Modified(_) | Summary::Accessed(_), EventType::Create(c)) => Summary::Created(c),
+            (Summary::Modified(_) | Summary::Accessed(_), EventType::Delete(d)) => Summary::Deleted(d),
+            (Summary::Modified(m), EventType::Access(_)) => Summary::Modified(m),
+            // data changes are more relevant than metadata or unknown changes
+            (Summary::Modified(m @ EventType::ModifyData(_)), modify)
+                if !matches!(modify, EventType::ModifyData(_)) =>
+            {
+                Summary::Modified(m)
+            }
+            (Summary::Modified(_), modify) => Summary::Modified(modify),
+
+            (Summary::Accessed(_), EventType::Access(a)) => Summary::Accessed(a),
+            (Summary::Accessed(_), modify) => Summary::Modified(modify),
+        }
+    }
+
+    fn into_events(self) -> Vec<EventType> {
+        match self {
+            Summary::Created(c) => vec![EventType::Create(c)],
+            Summary::Deleted(d) => vec![EventType::Delete(d)],
+            Summary::Modified(m) => vec![m],
+            Summary::Accessed(a) => vec![EventType::Access(a)],
+            Summary::Replaced(d, c) => vec![EventType::Delete(d), EventType::Create(c)],
+            Summary::Vanished => Vec::new(),
+        }
+    }
+}
+
+/// Reduce events of each path in the batch to a canonical summary,
+/// e.g. create+modify becomes create, create+delete disappears and modify+delete becomes delete.
+///
+/// Rename and move events are kept as they are and split the summaries of their paths.
+/// Summaries are placed where the first event of their path was.
+pub(crate) fn collapse(events: Vec<EventType>) -> Vec<EventType> {
+    let mut slots: Vec<Option<Summary>> = Vec::with_capacity(events.len());
+    let mut renames: HashMap<usize, EventType> = HashMap::new();
+    let mut slot_by_path: HashMap<PathBuf, usize> = HashMap::new();
+
+    for event in events {
+        let path = match event {
+            EventType::Rename(ref e) => {
+                // close summaries of both paths, so events after the rename start over
+                slot_by_path.remove(&e.old_path);
+                slot_by_path.remove(&e.new_path);
+
+                renames.insert(slots.len(), event);
+                slots.push(None);
+                continue;
+            }
+            EventType::MovedIn(_) | EventType::MovedOut(_) => {
+                slot_by_path.remove(event.path().expect("move event must have a path"));
+
+                renames.insert(slots.len(), event);
+                slots.push(None);
+                continue;
+            }
+            _ => event.path().expect("non-rename event must have a path").to_path_buf(),
+        };
+
+        match slot_by_path.get(&path) {
+            Some(&slot) => {
+                let summary = slots[slot].take().expect("summary slot must be filled");
+                slots[slot] = Some(summary.push(event));
+            }
+            None => {
+                slot_by_path.insert(path, slots.len());
+                slots.push(Some(Summary::new(event)));
+            }
+        }
+    }
+
+    let mut collapsed = Vec::with_capacity(slots.len());
+
+    for (idx, slot) in slots.into_iter().enumerate() {
+        match slot {
+            Some(summary) => collapsed.extend(summary.into_events()),
+            None => collapsed.extend(renames.remove(&idx)),
+        }
+    }
+
+    collapsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::events::access::{AccessMode, AccessType};
+    use crate::events::base::ObjectType;
+    use crate::events::modify::{DataType, MetadataType, ModifyDataEvent, ModifyMetadataEvent};
+    use crate::events::moved::MovedOutEvent;
+    use crate::events::rename::RenameEvent;
+
+    fn create(path: &str) -> EventType {
+        EventType::Create(CreateEvent::new(PathBuf::from(path), ObjectType::File))
+    }
+
+    fn delete(path: &str) -> EventType {
+        EventType::Delete(DeleteEvent::new(PathBuf::from(path), ObjectType::File))
+    }
+
+    fn modify(path: &str) -> EventType {
+        EventType::ModifyData(ModifyDataEvent::new(PathBuf::from(path), DataType::Content))
+    }
+
+    fn metadata(path: &str) -> EventType {
+        EventType::ModifyMetadata(ModifyMetadataEvent::new(PathBuf::from(path), MetadataType::WriteTime))
+    }
+
+    fn close(path: &str) -> EventType {
+        EventType::Access(AccessEvent::new(
+            PathBuf::from(path),
+            AccessType::Close,
+            Some(AccessMode::Write),
+        ))
+    }
+
+    fn rename(old_path: &str, new_path: &str) -> EventType {
+        EventType::Rename(RenameEvent::new(
+            PathBuf::from(old_path),
+            PathBuf::from(new_path),
+            false,
+            Vec::new(),
+        ))
+    }
+
+    fn kinds(events: &[EventType]) -> Vec<&'static str> {
+        events.iter().map(EventType::kind).collect()
+    }
+
+    #[test]
+    fn test_create_and_modify() {
+        let events = collapse(vec![create("/a"), modify("/a"), metadata("/a"), close("/a")]);
+
+        assert_eq!(kinds(&events), vec!["create"]);
+    }
+
+    #[test]
+    fn test_create_and_delete() {
+        let events = collapse(vec![create("/a"), modify("/a"), delete("/a")]);
+
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_modify_and_delete() {
+        let events = collapse(vec![modify("/a"), delete("/a")]);
+
+        assert_eq!(kinds(&events), vec!["delete"]);
+    }
+
+    #[test]
+    fn test_delete_and_create() {
+        let events = collapse(vec![delete("/a"), create("/a"), modify("/a")]);
+
+        assert_eq!(kinds(&events), vec!["delete", "create"]);
+    }
+
+    #[test]
+    fn test_save_collapses_to_single_modify() {
+        let events = collapse(vec![modify("/a"), metadata("/a"), modify("/a"), close("/a")]);
+
+        assert_eq!(kinds(&events), vec!["modify_data"]);
+    }
+
+    #[test]
+    fn test_paths_keep_their_order() {
+        let events = collapse(vec![modify("/a"), create("/b"), modify("/a"), modify("/b")]);
+
+        assert_eq!(kinds(&events), vec!["modify_data", "create"]);
+        assert_eq!(events[1].path(), Some(PathBuf::from("/b").as_path()));
+    }
+
+    #[test]
+    fn test_rename_splits_summaries() {
+        let events = collapse(vec![create("/a"), rename("/a", "/b"), modify("/b"), modify("/b")]);
+
+        assert_eq!(kinds(&events), vec!["create", "rename", "modify_data"]);
+    }
+
+    #[test]
+    fn test_moves_split_summaries() {
+        let moved_out = EventType::MovedOut(MovedOutEvent::new(PathBuf::from("/a")));
+        let events = collapse(vec![create("/a"), modify("/a"), moved_out]);
+
+        assert_eq!(kinds(&events), vec!["create", "moved_out"]);
+    }
+}
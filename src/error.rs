@@ -0,0 +1,105 @@
+use std::fmt;
+use std::io::{self, ErrorKind as IOErrorKind};
+use std::sync::PoisonError;
+
+use notify::ErrorKind as NotifyErrorKind;
+
+/// Errors of the watcher and its subscriptions.
+#[derive(Debug)]
+pub enum WatchError {
+    /// The watcher was closed and can't be used anymore
+    Closed,
+    /// The path to watch doesn't exist (with the error message)
+    PathNotFound(String),
+    /// The path to watch can't be accessed (with the error message)
+    PermissionDenied(String),
+    /// The backend failed to watch or unwatch a path
+    Notify(notify::Error),
+    Io(io::Error),
+    /// The snapshot could not be read
+    Snapshot(io::Error),
+    InvalidFilter(regex::Error),
+    SubscriptionExists(String),
+    NoSuchSubscription(String),
+    /// The default subscription can't be removed
+    DefaultSubscription,
+    /// The consumer fell behind and the given number of event batches were dropped
+    Lagged(u64),
+    /// A thread panicked while holding the watcher's state
+    LockPoisoned(String),
+    /// A line of the recording of backend events could not be read (with the line number)
+    InvalidRecording(usize, serde_json::Error),
+}
+
+impl fmt::Display for WatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchError::Closed => write!(f, "watcher is closed"),
+            WatchError::PathNotFound(message) | WatchError::PermissionDenied(message) => write!(f, "{message}"),
+            WatchError::Notify(err) => write!(f, "{err}"),
+            WatchError::Io(err) => write!(f, "{err}"),
+            WatchError::Snapshot(err) => write!(f, "failed to load snapshot: {err}"),
+            WatchError::InvalidFilter(err) => write!(f, "invalid filter pattern: {err}"),
+            WatchError::SubscriptionExists(name) => write!(f, "subscription already exists: {name}"),
+            WatchError::NoSuchSubscription(name) => write!(f, "no such subscription: {name}"),
+            WatchError::DefaultSubscription => write!(f, "the default subscription can't be removed"),
+            WatchError::Lagged(n) => write!(f, "consumer too slow, {n} event batch(es) dropped"),
+            WatchError::LockPoisoned(message) => write!(f, "{message}"),
+            WatchError::InvalidRecording(line, err) => write!(f, "invalid recording at line {line}: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for WatchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WatchError::Notify(err) => Some(err),
+            WatchError::Io(err) | WatchError::Snapshot(err) => Some(err),
+            WatchError::InvalidFilter(err) => Some(err),
+            WatchError::InvalidRecording(_, err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<notify::Error> for WatchError {
+    fn from(notify_error: notify::Error) -> Self {
+        let err_str = notify_error.to_string();
+
+        match notify_error.kind {
+            NotifyErrorKind::PathNotFound => return WatchError::PathNotFound(err_str),
+            // on Windows, we get a Generic with this message when the path does not exist
+            NotifyErrorKind::Generic(ref err)
+                if err.as_str() == "Input watch path is neither a file nor a directory." =>
+            {
+                return WatchError::PathNotFound(err_str);
+            }
+            NotifyErrorKind::Io(ref io_error) => match io_error.kind() {
+                IOErrorKind::NotFound => return WatchError::PathNotFound(err_str),
+                IOErrorKind::PermissionDenied => return WatchError::PermissionDenied(err_str),
+                _ => (),
+            },
+            _ => (),
+        };
+
+        WatchError::Notify(notify_error)
+    }
+}
+
+impl From<io::Error> for WatchError {
+    fn from(err: io::Error) -> Self {
+        WatchError::Io(err)
+    }
+}
+
+impl From<regex::Error> for WatchError {
+    fn from(err: regex::Error) -> Self {
+        WatchError::InvalidFilter(err)
+    }
+}
+
+impl<T> From<PoisonError<T>> for WatchError {
+    fn from(err: PoisonError<T>) -> Self {
+        WatchError::LockPoisoned(err.to_string())
+    }
+}
@@ -1,10 +1,22 @@
+#[cfg(feature = "python")]
+use crate::events::base::{Reduce, ReduceEnum, reduce_enum};
 use notify::event::{AccessKind, AccessMode as NotifyAccessMode};
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::convert::From;
 use std::path::PathBuf;
 
-#[pyclass(rename_all = "SCREAMING_SNAKE_CASE", from_py_object)]
-#[derive(Debug, Copy, Clone)]
+#[cfg_attr(
+    feature = "python",
+    pyclass(
+        module = "notifykit._notifykit_lib",
+        rename_all = "SCREAMING_SNAKE_CASE",
+        from_py_object
+    )
+)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum AccessType {
     Unknown = 0,
     Read = 1,
@@ -13,6 +25,27 @@ pub enum AccessType {
     Other = 4,
 }
 
+impl AccessType {
+    /// The name of the value in Python
+    pub fn name(&self) -> &'static str {
+        match self {
+            AccessType::Unknown => "UNKNOWN",
+            AccessType::Read => "READ",
+            AccessType::Open => "OPEN",
+            AccessType::Close => "CLOSE",
+            AccessType::Other => "OTHER",
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl AccessType {
+    fn __reduce__<'py>(slf: &Bound<'py, Self>) -> PyResult<ReduceEnum<'py>> {
+        reduce_enum(slf.as_any(), slf.borrow().name())
+    }
+}
+
 impl From<AccessKind> for AccessType {
     fn from(kind: AccessKind) -> Self {
         match kind {
@@ -25,8 +58,16 @@ impl From<AccessKind> for AccessType {
     }
 }
 
-#[pyclass(rename_all = "SCREAMING_SNAKE_CASE", from_py_object)]
-#[derive(Debug, Copy, Clone)]
+#[cfg_attr(
+    feature = "python",
+    pyclass(
+        module = "notifykit._notifykit_lib",
+        rename_all = "SCREAMING_SNAKE_CASE",
+        from_py_object
+    )
+)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum AccessMode {
     Unknown = 0,
     Read = 1,
@@ -35,6 +76,27 @@ pub enum AccessMode {
     Other = 4,
 }
 
+impl AccessMode {
+    /// The name of the value in Python
+    pub fn name(&self) -> &'static str {
+        match self {
+            AccessMode::Unknown => "UNKNOWN",
+            AccessMode::Read => "READ",
+            AccessMode::Write => "WRITE",
+            AccessMode::Execute => "EXECUTE",
+            AccessMode::Other => "OTHER",
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl AccessMode {
+    fn __reduce__<'py>(slf: &Bound<'py, Self>) -> PyResult<ReduceEnum<'py>> {
+        reduce_enum(slf.as_any(), slf.borrow().name())
+    }
+}
+
 impl From<NotifyAccessMode> for AccessMode {
     fn from(kind: NotifyAccessMode) -> Self {
         match kind {
@@ -47,24 +109,18 @@ impl From<NotifyAccessMode> for AccessMode {
     }
 }
 
-#[pyclass(from_py_object)]
-#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "python",
+    pyclass(module = "notifykit._notifykit_lib", from_py_object, frozen, eq, hash, get_all)
+)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct AccessEvent {
-    #[pyo3(get)]
     pub path: PathBuf,
-    #[pyo3(get)]
     pub access_type: AccessType,
-    #[pyo3(get)]
     pub access_mode: Option<AccessMode>,
 }
 
-#[pymethods]
 impl AccessEvent {
-    #[classattr]
-    #[allow(non_upper_case_globals)]
-    const __match_args__: (&'static str, &'static str, &'static str) = ("path", "access_type", "access_mode");
-
-    #[new]
     pub fn new(path: PathBuf, access_type: AccessType, access_mode: Option<AccessMode>) -> Self {
         Self {
             path,
@@ -72,12 +128,33 @@ impl AccessEvent {
             access_mode,
         }
     }
+}
 
-    fn __repr__(&self) -> String {
-        format!(
-            "AccessEvent({:?}, {:?}, {:?})",
-            self.path, self.access_type, self.access_mode,
-        )
+#[cfg(feature = "python")]
+event_pymethods! {
+    impl AccessEvent {
+        #[classattr]
+        #[allow(non_upper_case_globals)]
+        const __match_args__: (&'static str, &'static str, &'static str) = ("path", "access_type", "access_mode");
+
+        #[new]
+        fn py_new(path: PathBuf, access_type: AccessType, access_mode: Option<AccessMode>) -> Self {
+            Self::new(path, access_type, access_mode)
+        }
+
+        fn __repr__(&self) -> String {
+            format!(
+                "AccessEvent({:?}, {:?}, {:?})",
+                self.path, self.access_type, self.access_mode,
+            )
+        }
+
+        fn __reduce__<'py>(&self, py: Python<'py>) -> Reduce<'py, (PathBuf, AccessType, Option<AccessMode>)> {
+            (
+                py.get_type::<Self>(),
+                (self.path.clone(), self.access_type, self.access_mode),
+            )
+        }
     }
 }
 
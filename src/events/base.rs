@@ -1,9 +1,21 @@
 use notify::event::{CreateKind, RemoveKind};
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::types::PyType;
+use serde::{Deserialize, Serialize};
 use std::convert::From;
 
-#[pyclass(rename_all = "SCREAMING_SNAKE_CASE", from_py_object)]
-#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "python",
+    pyclass(
+        module = "notifykit._notifykit_lib",
+        rename_all = "SCREAMING_SNAKE_CASE",
+        from_py_object
+    )
+)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum ObjectType {
     Unknown = 0,
     File = 1,
@@ -11,6 +23,26 @@ pub enum ObjectType {
     Other = 3,
 }
 
+impl ObjectType {
+    /// The name of the value in Python
+    pub fn name(&self) -> &'static str {
+        match self {
+            ObjectType::Unknown => "UNKNOWN",
+            ObjectType::File => "FILE",
+            ObjectType::Dir => "DIR",
+            ObjectType::Other => "OTHER",
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl ObjectType {
+    fn __reduce__<'py>(slf: &Bound<'py, Self>) -> PyResult<ReduceEnum<'py>> {
+        reduce_enum(slf.as_any(), slf.borrow().name())
+    }
+}
+
 impl From<CreateKind> for ObjectType {
     fn from(kind: CreateKind) -> Self {
         match kind {
@@ -32,3 +64,19 @@ impl From<RemoveKind> for ObjectType {
         }
     }
 }
+
+/// What `__reduce__` of event classes returns: the class and the arguments to construct an equal event with.
+#[cfg(feature = "python")]
+pub(crate) type Reduce<'py, Args> = (Bound<'py, PyType>, Args);
+
+/// What `__reduce__` of enums returns: `getattr` and the arguments to get the value from the enum class with.
+#[cfg(feature = "python")]
+pub(crate) type ReduceEnum<'py> = (Bound<'py, PyAny>, (Bound<'py, PyType>, &'static str));
+
+/// Enum values are pickled by name, so unpickling returns the values of the enum class rather than new objects.
+#[cfg(feature = "python")]
+pub(crate) fn reduce_enum<'py>(slf: &Bound<'py, PyAny>, name: &'static str) -> PyResult<ReduceEnum<'py>> {
+    let py = slf.py();
+
+    Ok((py.import("builtins")?.getattr("getattr")?, (slf.get_type(), name)))
+}
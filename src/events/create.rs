@@ -1,30 +1,47 @@
 use crate::events::base::ObjectType;
+#[cfg(feature = "python")]
+use crate::events::base::Reduce;
 use notify::event::CreateKind;
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
-#[pyclass(from_py_object)]
-#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "python",
+    pyclass(module = "notifykit._notifykit_lib", from_py_object, frozen, eq, hash, get_all)
+)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct CreateEvent {
-    #[pyo3(get)]
     pub path: PathBuf,
-    #[pyo3(get)]
     pub file_type: ObjectType,
 }
 
-#[pymethods]
 impl CreateEvent {
-    #[classattr]
-    #[allow(non_upper_case_globals)]
-    const __match_args__: (&'static str, &'static str) = ("path", "file_type");
-
-    #[new]
     pub fn new(path: PathBuf, file_type: ObjectType) -> Self {
         Self { path, file_type }
     }
+}
+
+#[cfg(feature = "python")]
+event_pymethods! {
+    impl CreateEvent {
+        #[classattr]
+        #[allow(non_upper_case_globals)]
+        const __match_args__: (&'static str, &'static str) = ("path", "file_type");
+
+        #[new]
+        fn py_new(path: PathBuf, file_type: ObjectType) -> Self {
+            Self::new(path, file_type)
+        }
+
+        fn __repr__(&self) -> String {
+            format!("CreateEvent({:?}, {:?})", self.path, self.file_type,)
+        }
 
-    fn __repr__(&self) -> String {
-        format!("CreateEvent({:?}, {:?})", self.path, self.file_type,)
+        fn __reduce__<'py>(&self, py: Python<'py>) -> Reduce<'py, (PathBuf, ObjectType)> {
+            (py.get_type::<Self>(), (self.path.clone(), self.file_type.clone()))
+        }
     }
 }
 
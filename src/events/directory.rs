@@ -0,0 +1,53 @@
+#[cfg(feature = "python")]
+use crate::events::base::Reduce;
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Many direct children of the directory changed within one batch and their events were rolled up.
+#[cfg_attr(
+    feature = "python",
+    pyclass(module = "notifykit._notifykit_lib", from_py_object, frozen, eq, hash, get_all)
+)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DirectoryChangedEvent {
+    pub path: PathBuf,
+    pub count: usize,
+    /// Kinds of the rolled up events (e.g. "create", "modify_data")
+    pub kinds: Vec<String>,
+}
+
+impl DirectoryChangedEvent {
+    pub fn new(path: PathBuf, count: usize, kinds: Vec<String>) -> Self {
+        Self { path, count, kinds }
+    }
+}
+
+#[cfg(feature = "python")]
+event_pymethods! {
+    impl DirectoryChangedEvent {
+        #[classattr]
+        #[allow(non_upper_case_globals)]
+        const __match_args__: (&'static str, &'static str, &'static str) = ("path", "count", "kinds");
+
+        #[new]
+        fn py_new(path: PathBuf, count: usize, kinds: Vec<String>) -> Self {
+            Self::new(path, count, kinds)
+        }
+
+        fn __repr__(&self) -> String {
+            format!(
+                "DirectoryChangedEvent({:?}, {:?}, {:?})",
+                self.path, self.count, self.kinds,
+            )
+        }
+
+        fn __reduce__<'py>(&self, py: Python<'py>) -> Reduce<'py, (PathBuf, usize, Vec<String>)> {
+            (
+                py.get_type::<Self>(),
+                (self.path.clone(), self.count, self.kinds.clone()),
+            )
+        }
+    }
+}
@@ -1,16 +1,59 @@
 use std::path::Path;
 
+#[cfg(feature = "python")]
 use pyo3::conversion::IntoPyObject;
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
-pub(crate) mod access;
-pub(crate) mod base;
-pub(crate) mod create;
-pub(crate) mod delete;
-pub(crate) mod modify;
-pub(crate) mod rename;
+/// Define the Python methods of an event class along with the conversions shared by all of them:
+/// `to_dict()`, `to_json()`, `from_dict()` and `from_json()` (see `serialize`).
+#[cfg(feature = "python")]
+macro_rules! event_pymethods {
+    (impl $event:ident { $($methods:tt)* }) => {
+        #[pyo3::pymethods]
+        impl $event {
+            $($methods)*
 
-#[derive(Debug, Clone)]
+            fn to_dict<'py>(&self, py: pyo3::Python<'py>) -> pyo3::PyResult<pyo3::Bound<'py, pyo3::PyAny>> {
+                $crate::events::serialize::to_dict(py, &self.clone().into())
+            }
+
+            fn to_json(&self) -> pyo3::PyResult<String> {
+                $crate::events::serialize::to_json(&self.clone().into())
+            }
+
+            #[staticmethod]
+            fn from_dict(data: &pyo3::Bound<'_, pyo3::PyAny>) -> pyo3::PyResult<Self> {
+                $crate::events::serialize::expect_kind($crate::events::serialize::from_dict(data)?)
+            }
+
+            #[staticmethod]
+            fn from_json(data: &str) -> pyo3::PyResult<Self> {
+                $crate::events::serialize::expect_kind($crate::events::serialize::from_json(data)?)
+            }
+        }
+    };
+}
+
+pub mod access;
+pub mod base;
+pub mod create;
+pub mod delete;
+pub mod directory;
+pub mod modify;
+pub mod moved;
+pub mod rename;
+pub mod rescan;
+#[cfg(feature = "python")]
+pub(crate) mod serialize;
+
+/// An event of any kind.
+///
+/// Events are serialized as objects with the `kind` field (see `kind()`) next to the fields of the event class,
+/// where paths are strings and enum values are names (e.g. `{"kind": "create", "path": "/a.txt", "file_type": "FILE"}`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
 pub enum EventType {
     Access(access::AccessEvent),
     Create(create::CreateEvent),
@@ -20,6 +63,10 @@ pub enum EventType {
     ModifyUnknown(modify::ModifyUnknownEvent),
     ModifyOther(modify::ModifyOtherEvent),
     Rename(rename::RenameEvent),
+    MovedIn(moved::MovedInEvent),
+    MovedOut(moved::MovedOutEvent),
+    Rescan(rescan::RescanEvent),
+    DirectoryChanged(directory::DirectoryChangedEvent),
 }
 
 impl EventType {
@@ -34,10 +81,81 @@ impl EventType {
             EventType::ModifyUnknown(e) => Some(&e.path),
             EventType::ModifyOther(e) => Some(&e.path),
             EventType::Rename(_) => None,
+            EventType::MovedIn(e) => Some(&e.path),
+            EventType::MovedOut(e) => Some(&e.path),
+            EventType::Rescan(e) => Some(&e.path),
+            EventType::DirectoryChanged(e) => Some(&e.path),
         }
     }
+
+    /// Returns a short name of the event kind.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            EventType::Access(_) => "access",
+            EventType::Create(_) => "create",
+            EventType::Delete(_) => "delete",
+            EventType::ModifyMetadata(_) => "modify_metadata",
+            EventType::ModifyData(_) => "modify_data",
+            EventType::ModifyUnknown(_) => "modify_unknown",
+            EventType::ModifyOther(_) => "modify_other",
+            EventType::Rename(_) => "rename",
+            EventType::MovedIn(_) => "moved_in",
+            EventType::MovedOut(_) => "moved_out",
+            EventType::Rescan(_) => "rescan",
+            EventType::DirectoryChanged(_) => "directory_changed",
+        }
+    }
+
+    /// Serializes the event to JSON (see the schema above).
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    /// Reads an event of any kind from JSON produced by `to_json()`.
+    pub fn from_json(data: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(data)
+    }
 }
 
+macro_rules! event_conversions {
+    ($($variant:ident($event:ty)),* $(,)?) => {
+        $(
+            impl From<$event> for EventType {
+                fn from(event: $event) -> Self {
+                    EventType::$variant(event)
+                }
+            }
+
+            impl TryFrom<EventType> for $event {
+                type Error = EventType;
+
+                fn try_from(event: EventType) -> Result<Self, Self::Error> {
+                    match event {
+                        EventType::$variant(event) => Ok(event),
+                        other => Err(other),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+event_conversions!(
+    Access(access::AccessEvent),
+    Create(create::CreateEvent),
+    Delete(delete::DeleteEvent),
+    ModifyMetadata(modify::ModifyMetadataEvent),
+    ModifyData(modify::ModifyDataEvent),
+    ModifyUnknown(modify::ModifyUnknownEvent),
+    ModifyOther(modify::ModifyOtherEvent),
+    Rename(rename::RenameEvent),
+    MovedIn(moved::MovedInEvent),
+    MovedOut(moved::MovedOutEvent),
+    Rescan(rescan::RescanEvent),
+    DirectoryChanged(directory::DirectoryChangedEvent),
+);
+
+#[cfg(feature = "python")]
 impl<'py> IntoPyObject<'py> for &EventType {
     type Target = PyAny;
     type Output = Bound<'py, PyAny>;
@@ -53,6 +171,10 @@ impl<'py> IntoPyObject<'py> for &EventType {
             EventType::ModifyOther(event) => Bound::new(py, event.clone())?.into_any(),
             EventType::ModifyUnknown(event) => Bound::new(py, event.clone())?.into_any(),
             EventType::Rename(event) => Bound::new(py, event.clone())?.into_any(),
+            EventType::MovedIn(event) => Bound::new(py, event.clone())?.into_any(),
+            EventType::MovedOut(event) => Bound::new(py, event.clone())?.into_any(),
+            EventType::Rescan(event) => Bound::new(py, event.clone())?.into_any(),
+            EventType::DirectoryChanged(event) => Bound::new(py, event.clone())?.into_any(),
         })
     }
 }
@@ -1,9 +1,21 @@
+#[cfg(feature = "python")]
+use crate::events::base::{Reduce, ReduceEnum, reduce_enum};
 use notify::event::{DataChange, MetadataKind};
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
-#[pyclass(rename_all = "SCREAMING_SNAKE_CASE", from_py_object)]
-#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "python",
+    pyclass(
+        module = "notifykit._notifykit_lib",
+        rename_all = "SCREAMING_SNAKE_CASE",
+        from_py_object
+    )
+)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum MetadataType {
     Unknown = 0,
     AccessTime = 1,
@@ -14,6 +26,29 @@ pub enum MetadataType {
     Other = 6,
 }
 
+impl MetadataType {
+    /// The name of the value in Python
+    pub fn name(&self) -> &'static str {
+        match self {
+            MetadataType::Unknown => "UNKNOWN",
+            MetadataType::AccessTime => "ACCESS_TIME",
+            MetadataType::WriteTime => "WRITE_TIME",
+            MetadataType::Ownership => "OWNERSHIP",
+            MetadataType::Permissions => "PERMISSIONS",
+            MetadataType::Extended => "EXTENDED",
+            MetadataType::Other => "OTHER",
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl MetadataType {
+    fn __reduce__<'py>(slf: &Bound<'py, Self>) -> PyResult<ReduceEnum<'py>> {
+        reduce_enum(slf.as_any(), slf.borrow().name())
+    }
+}
+
 impl From<MetadataKind> for MetadataType {
     fn from(kind: MetadataKind) -> Self {
         match kind {
@@ -28,8 +63,16 @@ impl From<MetadataKind> for MetadataType {
     }
 }
 
-#[pyclass(rename_all = "SCREAMING_SNAKE_CASE", from_py_object)]
-#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "python",
+    pyclass(
+        module = "notifykit._notifykit_lib",
+        rename_all = "SCREAMING_SNAKE_CASE",
+        from_py_object
+    )
+)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum DataType {
     Unknown = 0,
     Content = 1,
@@ -37,6 +80,26 @@ pub enum DataType {
     Other = 3,
 }
 
+impl DataType {
+    /// The name of the value in Python
+    pub fn name(&self) -> &'static str {
+        match self {
+            DataType::Unknown => "UNKNOWN",
+            DataType::Content => "CONTENT",
+            DataType::Size => "SIZE",
+            DataType::Other => "OTHER",
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl DataType {
+    fn __reduce__<'py>(slf: &Bound<'py, Self>) -> PyResult<ReduceEnum<'py>> {
+        reduce_enum(slf.as_any(), slf.borrow().name())
+    }
+}
+
 impl From<DataChange> for DataType {
     fn from(kind: DataChange) -> Self {
         match kind {
@@ -48,28 +111,41 @@ impl From<DataChange> for DataType {
     }
 }
 
-#[pyclass(from_py_object)]
-#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "python",
+    pyclass(module = "notifykit._notifykit_lib", from_py_object, frozen, eq, hash, get_all)
+)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ModifyDataEvent {
-    #[pyo3(get)]
     pub path: PathBuf,
-    #[pyo3(get)]
     pub data_type: DataType,
 }
 
-#[pymethods]
 impl ModifyDataEvent {
-    #[classattr]
-    #[allow(non_upper_case_globals)]
-    const __match_args__: (&'static str, &'static str) = ("path", "data_type");
-
-    #[new]
     pub fn new(path: PathBuf, data_type: DataType) -> Self {
         Self { path, data_type }
     }
+}
+
+#[cfg(feature = "python")]
+event_pymethods! {
+    impl ModifyDataEvent {
+        #[classattr]
+        #[allow(non_upper_case_globals)]
+        const __match_args__: (&'static str, &'static str) = ("path", "data_type");
 
-    fn __repr__(&self) -> String {
-        format!("ModifyDataEvent({:?}, {:?})", self.path, self.data_type,)
+        #[new]
+        fn py_new(path: PathBuf, data_type: DataType) -> Self {
+            Self::new(path, data_type)
+        }
+
+        fn __repr__(&self) -> String {
+            format!("ModifyDataEvent({:?}, {:?})", self.path, self.data_type,)
+        }
+
+        fn __reduce__<'py>(&self, py: Python<'py>) -> Reduce<'py, (PathBuf, DataType)> {
+            (py.get_type::<Self>(), (self.path.clone(), self.data_type.clone()))
+        }
     }
 }
 
@@ -80,28 +156,41 @@ pub fn from_data_kind(path: PathBuf, data_kind: DataChange) -> ModifyDataEvent {
     }
 }
 
-#[pyclass(from_py_object)]
-#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "python",
+    pyclass(module = "notifykit._notifykit_lib", from_py_object, frozen, eq, hash, get_all)
+)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ModifyMetadataEvent {
-    #[pyo3(get)]
     pub path: PathBuf,
-    #[pyo3(get)]
     pub metadata_type: MetadataType,
 }
 
-#[pymethods]
 impl ModifyMetadataEvent {
-    #[classattr]
-    #[allow(non_upper_case_globals)]
-    const __match_args__: (&'static str, &'static str) = ("path", "metadata_type");
-
-    #[new]
     pub fn new(path: PathBuf, metadata_type: MetadataType) -> Self {
         Self { path, metadata_type }
     }
+}
+
+#[cfg(feature = "python")]
+event_pymethods! {
+    impl ModifyMetadataEvent {
+        #[classattr]
+        #[allow(non_upper_case_globals)]
+        const __match_args__: (&'static str, &'static str) = ("path", "metadata_type");
+
+        #[new]
+        fn py_new(path: PathBuf, metadata_type: MetadataType) -> Self {
+            Self::new(path, metadata_type)
+        }
+
+        fn __repr__(&self) -> String {
+            format!("ModifyMetadataEvent({:?}, {:?})", self.path, self.metadata_type,)
+        }
 
-    fn __repr__(&self) -> String {
-        format!("ModifyMetadataEvent({:?}, {:?})", self.path, self.metadata_type,)
+        fn __reduce__<'py>(&self, py: Python<'py>) -> Reduce<'py, (PathBuf, MetadataType)> {
+            (py.get_type::<Self>(), (self.path.clone(), self.metadata_type.clone()))
+        }
     }
 }
 
@@ -112,48 +201,76 @@ pub fn from_metadata_kind(path: PathBuf, metadata_kind: MetadataKind) -> ModifyM
     }
 }
 
-#[pyclass(from_py_object)]
-#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "python",
+    pyclass(module = "notifykit._notifykit_lib", from_py_object, frozen, eq, hash, get_all)
+)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ModifyOtherEvent {
-    #[pyo3(get)]
     pub path: PathBuf,
 }
 
-#[pymethods]
 impl ModifyOtherEvent {
-    #[classattr]
-    #[allow(non_upper_case_globals)]
-    const __match_args__: (&'static str,) = ("path",);
-
-    #[new]
     pub fn new(path: PathBuf) -> Self {
         Self { path }
     }
+}
+
+#[cfg(feature = "python")]
+event_pymethods! {
+    impl ModifyOtherEvent {
+        #[classattr]
+        #[allow(non_upper_case_globals)]
+        const __match_args__: (&'static str,) = ("path",);
+
+        #[new]
+        fn py_new(path: PathBuf) -> Self {
+            Self::new(path)
+        }
+
+        fn __repr__(&self) -> String {
+            format!("ModifyOtherEvent({:?})", self.path)
+        }
 
-    fn __repr__(&self) -> String {
-        format!("ModifyOtherEvent({:?})", self.path)
+        fn __reduce__<'py>(&self, py: Python<'py>) -> Reduce<'py, (PathBuf,)> {
+            (py.get_type::<Self>(), (self.path.clone(),))
+        }
     }
 }
 
-#[pyclass(from_py_object)]
-#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "python",
+    pyclass(module = "notifykit._notifykit_lib", from_py_object, frozen, eq, hash, get_all)
+)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ModifyUnknownEvent {
-    #[pyo3(get)]
     pub path: PathBuf,
 }
 
-#[pymethods]
 impl ModifyUnknownEvent {
-    #[classattr]
-    #[allow(non_upper_case_globals)]
-    const __match_args__: (&'static str,) = ("path",);
-
-    #[new]
     pub fn new(path: PathBuf) -> Self {
         Self { path }
     }
+}
 
-    fn __repr__(&self) -> String {
-        format!("ModifyUnknownEvent({:?})", self.path)
+#[cfg(feature = "python")]
+event_pymethods! {
+    impl ModifyUnknownEvent {
+        #[classattr]
+        #[allow(non_upper_case_globals)]
+        const __match_args__: (&'static str,) = ("path",);
+
+        #[new]
+        fn py_new(path: PathBuf) -> Self {
+            Self::new(path)
+        }
+
+        fn __repr__(&self) -> String {
+            format!("ModifyUnknownEvent({:?})", self.path)
+        }
+
+        fn __reduce__<'py>(&self, py: Python<'py>) -> Reduce<'py, (PathBuf,)> {
+            (py.get_type::<Self>(), (self.path.clone(),))
+        }
     }
 }
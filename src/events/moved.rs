@@ -0,0 +1,82 @@
+#[cfg(feature = "python")]
+use crate::events::base::Reduce;
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// A file was moved into the watched paths from a location that is not watched.
+#[cfg_attr(
+    feature = "python",
+    pyclass(module = "notifykit._notifykit_lib", from_py_object, frozen, eq, hash, get_all)
+)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MovedInEvent {
+    pub path: PathBuf,
+}
+
+impl MovedInEvent {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+#[cfg(feature = "python")]
+event_pymethods! {
+    impl MovedInEvent {
+        #[classattr]
+        #[allow(non_upper_case_globals)]
+        const __match_args__: (&'static str,) = ("path",);
+
+        #[new]
+        fn py_new(path: PathBuf) -> Self {
+            Self::new(path)
+        }
+
+        fn __repr__(&self) -> String {
+            format!("MovedInEvent({:?})", self.path)
+        }
+
+        fn __reduce__<'py>(&self, py: Python<'py>) -> Reduce<'py, (PathBuf,)> {
+            (py.get_type::<Self>(), (self.path.clone(),))
+        }
+    }
+}
+
+/// A file was moved out of the watched paths to a location that is not watched.
+#[cfg_attr(
+    feature = "python",
+    pyclass(module = "notifykit._notifykit_lib", from_py_object, frozen, eq, hash, get_all)
+)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MovedOutEvent {
+    pub path: PathBuf,
+}
+
+impl MovedOutEvent {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+#[cfg(feature = "python")]
+event_pymethods! {
+    impl MovedOutEvent {
+        #[classattr]
+        #[allow(non_upper_case_globals)]
+        const __match_args__: (&'static str,) = ("path",);
+
+        #[new]
+        fn py_new(path: PathBuf) -> Self {
+            Self::new(path)
+        }
+
+        fn __repr__(&self) -> String {
+            format!("MovedOutEvent({:?})", self.path)
+        }
+
+        fn __reduce__<'py>(&self, py: Python<'py>) -> Reduce<'py, (PathBuf,)> {
+            (py.get_type::<Self>(), (self.path.clone(),))
+        }
+    }
+}
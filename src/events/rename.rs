@@ -1,31 +1,77 @@
+#[cfg(feature = "python")]
+use crate::events::base::Reduce;
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
-#[pyclass(from_py_object)]
-#[derive(Debug, Clone)]
+/// Arguments of `RenameEvent::new`
+#[cfg(feature = "python")]
+type RenameArgs = (PathBuf, PathBuf, bool, Vec<(PathBuf, PathBuf)>);
+
+#[cfg_attr(
+    feature = "python",
+    pyclass(module = "notifykit._notifykit_lib", from_py_object, frozen, eq, hash, get_all)
+)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct RenameEvent {
-    #[pyo3(get)]
     pub old_path: PathBuf,
-    #[pyo3(get)]
     pub new_path: PathBuf,
+    #[serde(default)]
+    pub is_dir: bool,
+    /// Old and new paths of the renamed directory's children (when directory renames are expanded)
+    #[serde(default)]
+    pub children: Vec<(PathBuf, PathBuf)>,
 }
 
-#[pymethods]
 impl RenameEvent {
-    #[classattr]
-    #[allow(non_upper_case_globals)]
-    const __match_args__: (&'static str, &'static str) = ("old_path", "new_path");
-
-    #[new]
-    pub fn new(old_path: PathBuf, new_path: PathBuf) -> Self {
-        Self { old_path, new_path }
+    pub fn new(old_path: PathBuf, new_path: PathBuf, is_dir: bool, children: Vec<(PathBuf, PathBuf)>) -> Self {
+        Self {
+            old_path,
+            new_path,
+            is_dir,
+            children,
+        }
     }
+}
+
+#[cfg(feature = "python")]
+event_pymethods! {
+    impl RenameEvent {
+        #[classattr]
+        #[allow(non_upper_case_globals)]
+        const __match_args__: (&'static str, &'static str) = ("old_path", "new_path");
 
-    fn __repr__(&self) -> String {
-        format!("RenameEvent({:?}, {:?})", self.old_path, self.new_path,)
+        #[new]
+        #[pyo3(signature = (old_path, new_path, is_dir=false, children=vec![]))]
+        fn py_new(old_path: PathBuf, new_path: PathBuf, is_dir: bool, children: Vec<(PathBuf, PathBuf)>) -> Self {
+            Self::new(old_path, new_path, is_dir, children)
+        }
+
+        fn __repr__(&self) -> String {
+            format!("RenameEvent({:?}, {:?})", self.old_path, self.new_path,)
+        }
+
+        fn __reduce__<'py>(&self, py: Python<'py>) -> Reduce<'py, RenameArgs> {
+            (
+                py.get_type::<Self>(),
+                (
+                    self.old_path.clone(),
+                    self.new_path.clone(),
+                    self.is_dir,
+                    self.children.clone(),
+                ),
+            )
+        }
     }
 }
 
-pub fn from_rename_mode(old_path: PathBuf, new_path: PathBuf) -> RenameEvent {
-    RenameEvent { old_path, new_path }
+/// `is_dir` is the type of the path known from a file cache, otherwise the new path is checked on disk.
+pub fn from_rename_mode(old_path: PathBuf, new_path: PathBuf, is_dir: Option<bool>) -> RenameEvent {
+    RenameEvent {
+        is_dir: is_dir.unwrap_or_else(|| new_path.is_dir()),
+        old_path,
+        new_path,
+        children: Vec::new(),
+    }
 }
@@ -0,0 +1,46 @@
+#[cfg(feature = "python")]
+use crate::events::base::Reduce;
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Too many changes happened under the path at once to report them one by one,
+/// so the subtree should be rescanned instead.
+#[cfg_attr(
+    feature = "python",
+    pyclass(module = "notifykit._notifykit_lib", from_py_object, frozen, eq, hash, get_all)
+)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RescanEvent {
+    pub path: PathBuf,
+    pub count: usize,
+}
+
+impl RescanEvent {
+    pub fn new(path: PathBuf, count: usize) -> Self {
+        Self { path, count }
+    }
+}
+
+#[cfg(feature = "python")]
+event_pymethods! {
+    impl RescanEvent {
+        #[classattr]
+        #[allow(non_upper_case_globals)]
+        const __match_args__: (&'static str, &'static str) = ("path", "count");
+
+        #[new]
+        fn py_new(path: PathBuf, count: usize) -> Self {
+            Self::new(path, count)
+        }
+
+        fn __repr__(&self) -> String {
+            format!("RescanEvent({:?}, {:?})", self.path, self.count,)
+        }
+
+        fn __reduce__<'py>(&self, py: Python<'py>) -> Reduce<'py, (PathBuf, usize)> {
+            (py.get_type::<Self>(), (self.path.clone(), self.count))
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple};
+use serde_json::{Map, Number, Value};
+
+use crate::events::EventType;
+
+/// Convert the event into a dict with the `kind` field.
+pub(crate) fn to_dict<'py>(py: Python<'py>, event: &EventType) -> PyResult<Bound<'py, PyAny>> {
+    let value = serde_json::to_value(event).map_err(|e| PyValueError::new_err(e.to_string()))?;
+
+    to_py(py, &value)
+}
+
+pub(crate) fn to_json(event: &EventType) -> PyResult<String> {
+    serde_json::to_string(event).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Read an event of any kind from a dict produced by `to_dict()`.
+pub(crate) fn from_dict(data: &Bound<'_, PyAny>) -> PyResult<EventType> {
+    serde_json::from_value(from_py(data)?).map_err(|e| PyValueError::new_err(format!("invalid event: {e}")))
+}
+
+/// Read an event of any kind from JSON produced by `to_json()`.
+pub(crate) fn from_json(data: &str) -> PyResult<EventType> {
+    serde_json::from_str(data).map_err(|e| PyValueError::new_err(format!("invalid event: {e}")))
+}
+
+/// Unwrap the event class, failing if the event is of another kind.
+pub(crate) fn expect_kind<E: TryFrom<EventType, Error = EventType>>(event: EventType) -> PyResult<E> {
+    E::try_from(event).map_err(|other| PyValueError::new_err(format!("unexpected event kind: {}", other.kind())))
+}
+
+fn to_py<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        Value::Null => py.None().into_bound(py),
+        Value::Bool(value) => PyBool::new(py, *value).to_owned().into_any(),
+        Value::Number(number) => match (number.as_u64(), number.as_i64()) {
+            (Some(value), _) => value.into_pyobject(py)?.into_any(),
+            (_, Some(value)) => value.into_pyobject(py)?.into_any(),
+            _ => number.as_f64().unwrap_or_default().into_pyobject(py)?.into_any(),
+        },
+        Value::String(value) => PyString::new(py, value).into_any(),
+        Value::Array(items) => {
+            let items = items.iter().map(|item| to_py(py, item)).collect::<PyResult<Vec<_>>>()?;
+
+            PyList::new(py, items)?.into_any()
+        }
+        Value::Object(fields) => {
+            let dict = PyDict::new(py);
+
+            for (key, value) in fields {
+                dict.set_item(key, to_py(py, value)?)?;
+            }
+
+            dict.into_any()
+        }
+    })
+}
+
+/// Paths can be passed as `os.PathLike` objects, not only as strings.
+fn from_py(data: &Bound<'_, PyAny>) -> PyResult<Value> {
+    if data.is_none() {
+        return Ok(Value::Null);
+    }
+
+    // bool is a subclass of int, so it goes first
+    if let Ok(value) = data.cast::<PyBool>() {
+        return Ok(Value::Bool(value.is_true()));
+    }
+
+    if data.is_instance_of::<PyInt>() {
+        return Ok(match data.extract::<u64>() {
+            Ok(value) => Value::from(value),
+            Err(_) => Value::from(data.extract::<i64>()?),
+        });
+    }
+
+    if data.is_instance_of::<PyFloat>() {
+        return Ok(Number::from_f64(data.extract()?).map_or(Value::Null, Value::Number));
+    }
+
+    if let Ok(value) = data.cast::<PyString>() {
+        return Ok(Value::String(value.to_str()?.to_string()));
+    }
+
+    if let Ok(dict) = data.cast::<PyDict>() {
+        let mut fields = Map::new();
+
+        for (key, value) in dict.iter() {
+            fields.insert(key.extract::<String>()?, from_py(&value)?);
+        }
+
+        return Ok(Value::Object(fields));
+    }
+
+    if data.is_instance_of::<PyList>() || data.is_instance_of::<PyTuple>() {
+        let items = data
+            .try_iter()?
+            .map(|item| from_py(&item?))
+            .collect::<PyResult<Vec<_>>>()?;
+
+        return Ok(Value::Array(items));
+    }
+
+    if data.hasattr("__fspath__")? {
+        return from_py(&data.call_method0("__fspath__")?);
+    }
+
+    Err(PyTypeError::new_err(format!(
+        "unsupported value in event data: {}",
+        data.get_type().name()?
+    )))
+}
+
+/// Read an event of any kind from a dict produced by `to_dict()`.
+#[pyfunction]
+pub fn event_from_dict(py: Python<'_>, data: &Bound<'_, PyAny>) -> PyResult<Py<PyAny>> {
+    Ok((&from_dict(data)?).into_pyobject(py)?.unbind())
+}
+
+/// Read an event of any kind from JSON produced by `to_json()`.
+#[pyfunction]
+pub fn event_from_json(py: Python<'_>, data: &str) -> PyResult<Py<PyAny>> {
+    Ok((&from_json(data)?).into_pyobject(py)?.unbind())
+}
@@ -1,12 +1,26 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
+    fs::{self, Metadata},
+    io,
+    ops::Bound,
     path::{Path, PathBuf},
+    time::SystemTime,
 };
 
 use file_id::{FileId, get_file_id};
-use notify::RecursiveMode;
+use notify::event::{CreateKind, DataChange, MetadataKind, ModifyKind, RemoveKind, RenameMode};
+use notify::{Event as NotifyEvent, EventKind, RecursiveMode};
+use serde::{Deserialize, Serialize};
 use walkdir::WalkDir;
 
+use crate::events::EventType;
+use crate::events::base::ObjectType;
+use crate::events::create::CreateEvent;
+use crate::events::delete::DeleteEvent;
+use crate::events::modify::{DataType, ModifyDataEvent};
+use crate::events::rename::RenameEvent;
+use crate::filter::EventFilter;
+
 /// The interface of a file ID cache.
 ///
 /// This trait can be implemented for an existing cache, if it already holds `FileId`s.
@@ -32,14 +46,161 @@ pub trait FileIdCache {
     fn rescan(&mut self);
 }
 
+/// The interface of a cache that also holds the metadata of paths.
+///
+/// Deleted paths can't be inspected on disk anymore, so the cache is the only place to learn what they were.
+#[allow(dead_code)]
+pub trait FileMetadataCache: FileIdCache {
+    /// Get what is known about a given `path`.
+    ///
+    /// If the path is not cached, `None` should be returned and there should not be any attempt to read it from disk.
+    fn get_entry(&self, path: &Path) -> Option<&FileEntry>;
+
+    /// Whether the cached path is a directory or `None` if the path is not cached.
+    fn is_dir(&self, path: &Path) -> Option<bool> {
+        self.get_entry(path).map(|entry| entry.file_type == ObjectType::Dir)
+    }
+
+    /// Returns the cached direct children of a directory in sorted order.
+    fn children(&self, path: &Path) -> Vec<PathBuf>;
+}
+
+/// What is known about a cached path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileEntry {
+    pub file_id: FileId,
+    pub file_type: ObjectType,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+impl FileEntry {
+    fn new(file_id: FileId, metadata: &Metadata) -> Self {
+        let file_type = if metadata.is_dir() {
+            ObjectType::Dir
+        } else if metadata.is_file() {
+            ObjectType::File
+        } else {
+            ObjectType::Other
+        };
+
+        Self {
+            file_id,
+            file_type,
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        }
+    }
+
+    /// Whether the content of the file has changed, judging by its size and modification time.
+    ///
+    /// Directories are never reported as modified, since their modification time changes with their children.
+    fn is_modified(&self, previous: &FileEntry) -> bool {
+        self.file_type != ObjectType::Dir && (self.size != previous.size || self.modified != previous.modified)
+    }
+}
+
+/// A path as it's saved in snapshots: a string if it's valid Unicode,
+/// otherwise its bytes on Unix or UTF-16 code units on Windows, so no path is lost.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum SavedPath {
+    Unicode(String),
+    #[cfg(not(windows))]
+    Raw(Vec<u8>),
+    #[cfg(windows)]
+    Raw(Vec<u16>),
+}
+
+impl From<&Path> for SavedPath {
+    fn from(path: &Path) -> Self {
+        if let Some(path) = path.to_str() {
+            return SavedPath::Unicode(path.to_string());
+        }
+
+        #[cfg(not(windows))]
+        let raw = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str()).to_vec();
+        #[cfg(windows)]
+        let raw = std::os::windows::ffi::OsStrExt::encode_wide(path.as_os_str()).collect();
+
+        SavedPath::Raw(raw)
+    }
+}
+
+impl From<SavedPath> for PathBuf {
+    fn from(path: SavedPath) -> Self {
+        match path {
+            SavedPath::Unicode(path) => PathBuf::from(path),
+            #[cfg(not(windows))]
+            SavedPath::Raw(raw) => {
+                PathBuf::from(<std::ffi::OsString as std::os::unix::ffi::OsStringExt>::from_vec(raw))
+            }
+            #[cfg(windows)]
+            SavedPath::Raw(raw) => PathBuf::from(
+                <std::ffi::OsString as std::os::windows::ffi::OsStringExt>::from_wide(&raw),
+            ),
+        }
+    }
+}
+
+/// A walk of the cached paths after the notification back-end has dropped events.
+#[derive(Debug)]
+pub struct Rescan {
+    /// Paths to walk along with whether to walk them recursively
+    scope: Vec<(PathBuf, bool)>,
+    filter: Option<EventFilter>,
+    entries: HashMap<PathBuf, FileEntry>,
+}
+
+impl Rescan {
+    /// Add the scope of another rescan, so both are done with one walk.
+    ///
+    /// Paths already covered by the scope are skipped, and the ones the other scope covers are dropped.
+    pub fn merge(&mut self, other: Rescan) {
+        let covers = |(root, is_recursive): &(PathBuf, bool), (path, is_path_recursive): &(PathBuf, bool)| {
+            (root == path && (*is_recursive || !*is_path_recursive)) || (*is_recursive && path.starts_with(root))
+        };
+
+        for scope in other.scope {
+            if self.scope.iter().any(|root| covers(root, &scope)) {
+                continue;
+            }
+
+            self.scope.retain(|path| !covers(&scope, path));
+            self.scope.push(scope);
+        }
+    }
+
+    /// Read the current state of the paths in scope from disk.
+    pub fn walk(&mut self) {
+        for (path, is_recursive) in &self.scope {
+            self.entries.extend(walk(path, *is_recursive, self.filter.as_ref()));
+        }
+    }
+}
+
+/// What the file cache does to back-end events besides keeping track of them (see `FileCache::intake()`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheOptions {
+    /// Report the removal of every cached child of a removed directory
+    pub expand_dir_deletes: bool,
+    /// Drop metadata changes that don't change anything worth reporting (see `FileCache::is_noise()`)
+    pub ignore_noise: bool,
+}
+
 /// A cache to hold the file system IDs of all watched files.
 ///
 /// The file ID cache uses unique file IDs provided by the file system and is used to stich together
 /// rename events in case the notification back-end doesn't emit rename cookies.
 #[derive(Debug, Clone, Default)]
 pub struct FileCache {
-    file_ids_by_path: HashMap<PathBuf, FileId>,
+    /// Sorted by path, so a directory is followed by its descendants
+    entries_by_path: BTreeMap<PathBuf, FileEntry>,
     roots: Vec<(PathBuf, RecursiveMode)>,
+    /// The source path of a rename waiting for its target path
+    pending_rename: Option<PathBuf>,
+    /// Paths matching the filter (and their children) are not cached
+    filter: Option<EventFilter>,
 }
 
 #[allow(unused)]
@@ -49,6 +210,23 @@ impl FileCache {
         Default::default()
     }
 
+    /// Construct an empty cache that skips paths matching the filter.
+    pub fn with_filter(filter: EventFilter) -> Self {
+        Self {
+            filter: Some(filter),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the number of cached paths.
+    pub fn len(&self) -> usize {
+        self.entries_by_path.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries_by_path.is_empty()
+    }
+
     /// Add a path to the cache.
     ///
     /// If `recursive_mode` is `Recursive`, all children will be added to the cache as well
@@ -71,23 +249,356 @@ impl FileCache {
         self.remove_path(path.as_ref());
     }
 
-    fn dir_scan_depth(is_recursive: bool) -> usize {
-        if is_recursive {
-            return usize::MAX;
+    /// Read a cache saved with `save()`.
+    ///
+    /// The loaded cache has no roots and is meant to be compared with the current state via `diff_since()`.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let data = fs::read(path)?;
+        let entries: Vec<(SavedPath, FileEntry)> = serde_json::from_slice(&data)?;
+
+        Ok(Self {
+            entries_by_path: entries.into_iter().map(|(path, entry)| (path.into(), entry)).collect(),
+            ..Default::default()
+        })
+    }
+
+    /// Write paths of the cache along with their file IDs, sizes and modification times to disk.
+    ///
+    /// Paths that are not valid Unicode are saved as they are (see `SavedPath`).
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let entries: Vec<(SavedPath, &FileEntry)> = self
+            .entries_by_path
+            .iter()
+            .map(|(path, entry)| (SavedPath::from(path.as_path()), entry))
+            .collect();
+
+        fs::write(path, serde_json::to_vec(&entries)?)
+    }
+
+    /// Returns events that turn the `snapshot` state into the current state of the cache.
+    ///
+    /// Paths that got a new location, but kept their file ID, are reported as renames.
+    /// Children of renamed directories are covered by the directory rename.
+    /// Hard links of a file share its ID, so each moved link is paired with one of the old links.
+    /// File systems may reuse IDs of deleted files, so a deleted and a created file may be reported as a rename.
+    pub fn diff_since(&self, snapshot: &FileCache) -> Vec<EventType> {
+        diff(&snapshot.entries_by_path, &self.entries_by_path)
+    }
+
+    /// Returns all cached paths under the given directory (excluding the directory itself).
+    pub fn descendants(&self, path: &Path) -> Vec<PathBuf> {
+        self.subtree(path).filter(|p| p.as_path() != path).cloned().collect()
+    }
+
+    /// Returns the cached path and all cached paths under it in sorted order.
+    ///
+    /// Only the entries of the subtree are visited, not the whole cache.
+    fn subtree<'a>(&'a self, path: &'a Path) -> impl Iterator<Item = &'a PathBuf> + 'a {
+        self.entries_by_path
+            .range::<Path, _>((Bound::Included(path), Bound::Unbounded))
+            .map(|(p, _)| p)
+            .take_while(move |p| p.starts_with(path))
+    }
+
+    /// Returns old and new paths of the cached children of a directory that was moved from `old_path` to `new_path`.
+    ///
+    /// The cache must be already updated with the rename.
+    pub fn renamed_children(&self, old_path: &Path, new_path: &Path) -> Vec<(PathBuf, PathBuf)> {
+        self.descendants(new_path)
+            .into_iter()
+            .map(|path| {
+                let relative = path
+                    .strip_prefix(new_path)
+                    .expect("descendant must be under its directory");
+
+                (old_path.join(relative), path)
+            })
+            .collect()
+    }
+
+    /// Move the path and all its children to the new location.
+    ///
+    /// Paths that were not cached are read from disk.
+    pub fn rename_path(&mut self, from: &Path, to: &Path) {
+        let moved: Vec<PathBuf> = self.subtree(from).cloned().collect();
+
+        if moved.is_empty() {
+            self.add_path(to);
+            return;
         }
 
-        1
+        for old_path in moved {
+            if let Some(entry) = self.entries_by_path.remove(&old_path) {
+                let relative = old_path
+                    .strip_prefix(from)
+                    .expect("moved path must be under the source path");
+
+                self.entries_by_path.insert(to.join(relative), entry);
+            }
+        }
     }
-}
 
-impl FileIdCache for FileCache {
-    fn get_file_id(&self, path: &Path) -> Option<&FileId> {
-        self.file_ids_by_path.get(path)
+    /// Keep the cache up-to-date with a change reported by the notification back-end.
+    ///
+    /// The two halves of renames are expected to come one after another.
+    pub fn handle_event(&mut self, event: &NotifyEvent) {
+        let Some(path) = event.paths.first() else {
+            return;
+        };
+
+        let mut pending_rename = self.pending_rename.take();
+
+        let rename_mode = match event.kind {
+            EventKind::Modify(ModifyKind::Name(RenameMode::Any)) if event.paths.len() == 1 => {
+                if path.exists() {
+                    RenameMode::To
+                } else {
+                    RenameMode::From
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(mode)) => mode,
+            EventKind::Create(_) => {
+                self.add_path(path);
+                RenameMode::Other
+            }
+            EventKind::Remove(_) => {
+                self.remove_path(path);
+                RenameMode::Other
+            }
+            EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Metadata(_) | ModifyKind::Any) => {
+                self.refresh(path);
+                RenameMode::Other
+            }
+            _ => RenameMode::Other,
+        };
+
+        match rename_mode {
+            RenameMode::From => self.pending_rename = Some(path.clone()),
+            RenameMode::To => match pending_rename.take() {
+                Some(from) => self.rename_path(&from, path),
+                None => self.add_path(path),
+            },
+            // the backend reports both paths after the halves, which have already moved the paths
+            RenameMode::Both if event.paths.len() == 2 && self.get_file_id(&event.paths[1]).is_none() => {
+                self.rename_path(path, &event.paths[1]);
+            }
+            _ => {}
+        }
+
+        // a rename source without its target means the path was moved out of the watched paths
+        if let Some(from) = pending_rename {
+            self.remove_path(&from);
+        }
     }
 
-    fn add_path(&mut self, path: &Path) {
-        let is_recursive = self
-            .roots
+    /// Fill in what the back-end couldn't tell about the event from the cached state of its path.
+    ///
+    /// Removals of unknown objects get the type the path had before it was removed,
+    /// so this must be called before the event is applied to the cache.
+    pub fn classify(&self, mut event: NotifyEvent) -> NotifyEvent {
+        if let EventKind::Remove(RemoveKind::Any | RemoveKind::Other) = event.kind {
+            let kind = event
+                .paths
+                .first()
+                .and_then(|path| self.get_entry(path))
+                .map(|entry| remove_kind(&entry.file_type));
+
+            if let Some(kind) = kind {
+                event.kind = EventKind::Remove(kind);
+            }
+        }
+
+        event
+    }
+
+    /// Returns removal events for the cached children of a removed directory, each directory after its children.
+    ///
+    /// Back-ends may report only the removal of the top directory, so this must be called before the event is applied
+    /// to the cache. Children the back-end has already reported are not in the cache anymore.
+    pub fn removed_children(&self, event: &NotifyEvent) -> Vec<NotifyEvent> {
+        let Some(path) = event.paths.first() else {
+            return Vec::new();
+        };
+
+        if !matches!(event.kind, EventKind::Remove(_)) || self.is_dir(path) != Some(true) {
+            return Vec::new();
+        }
+
+        // a reversed sort puts every path after its descendants
+        self.descendants(path)
+            .into_iter()
+            .rev()
+            .map(|child| {
+                let kind = remove_kind(&self.entries_by_path[&child].file_type);
+
+                NotifyEvent::new(EventKind::Remove(kind)).add_path(child)
+            })
+            .collect()
+    }
+
+    /// Run a back-end event through the cache before it's handed over to event processors.
+    ///
+    /// Returns nothing for noise, otherwise the event along with the removals of its children,
+    /// each before its directory, as if they were removed one by one. The cache is up-to-date with the event afterwards.
+    /// Rescans are up to the caller, as they walk the disk.
+    pub fn intake(&mut self, event: NotifyEvent, options: CacheOptions) -> Vec<NotifyEvent> {
+        if options.ignore_noise && self.is_noise(&event) {
+            return Vec::new();
+        }
+
+        // deleted paths can only be classified before they're removed from the cache
+        let event = self.classify(event);
+
+        let mut events = match options.expand_dir_deletes {
+            true => self.removed_children(&event),
+            false => Vec::new(),
+        };
+
+        self.handle_event(&event);
+        events.push(event);
+
+        events
+    }
+
+    /// Whether the event is a metadata change that doesn't change anything worth reporting.
+    ///
+    /// Access time updates are not tracked, and modification time updates are stale if the cached time is still current.
+    pub fn is_noise(&self, event: &NotifyEvent) -> bool {
+        let Some(path) = event.paths.first() else {
+            return false;
+        };
+
+        match event.kind {
+            EventKind::Modify(ModifyKind::Metadata(MetadataKind::AccessTime)) => true,
+            EventKind::Modify(ModifyKind::Metadata(MetadataKind::WriteTime)) => {
+                self.get_entry(path).is_some_and(|entry| {
+                    entry.modified.is_some()
+                        && fs::metadata(path).is_ok_and(|metadata| metadata.modified().ok() == entry.modified)
+                })
+            }
+            _ => false,
+        }
+    }
+
+    /// Re-read the metadata of a cached path.
+    fn refresh(&mut self, path: &Path) {
+        let Some(entry) = self.entries_by_path.get_mut(path) else {
+            return;
+        };
+
+        if let Some(current) = read_entry(path) {
+            *entry = current;
+        }
+    }
+
+    /// Prepare a rescan of the subtree under the given path or of all roots if there is no path.
+    ///
+    /// The walk doesn't need the cache, so it can run without holding it while events keep coming in.
+    pub fn start_rescan(&self, path: Option<&Path>) -> Rescan {
+        let root = path.and_then(|path| self.roots.iter().find(|(root, _)| path.starts_with(root)));
+
+        let scope = match (path, root) {
+            (Some(path), Some((_, RecursiveMode::Recursive))) => vec![(path.to_path_buf(), true)],
+            // children of a non-recursive root are only reachable by walking the root itself
+            (Some(_), Some((root, _))) => vec![(root.clone(), false)],
+            _ => self
+                .roots
+                .iter()
+                .map(|(root, recursive_mode)| (root.clone(), *recursive_mode == RecursiveMode::Recursive))
+                .collect(),
+        };
+
+        Rescan {
+            scope,
+            filter: self.filter.clone(),
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Bring the cache in line with a finished walk.
+    ///
+    /// Returns synthetic events for the changes the back-end didn't report.
+    /// Each discrepancy is checked against the disk again, since the cache may have been updated after the walk.
+    pub fn finish_rescan(&mut self, rescan: Rescan) -> Vec<NotifyEvent> {
+        let in_scope = |path: &Path| {
+            rescan.scope.iter().any(|(root, is_recursive)| {
+                path.starts_with(root) && (*is_recursive || path == root || path.parent() == Some(root.as_path()))
+            })
+        };
+
+        let mut candidates: Vec<PathBuf> = rescan
+            .entries
+            .iter()
+            .filter(|(path, entry)| {
+                self.entries_by_path
+                    .get(*path)
+                    .is_none_or(|cached| cached.file_id != entry.file_id || entry.is_modified(cached))
+            })
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        candidates.extend(
+            self.entries_by_path
+                .keys()
+                .filter(|path| in_scope(path) && !rescan.entries.contains_key(*path))
+                .cloned(),
+        );
+
+        let mut removed = Vec::new();
+        let mut created = Vec::new();
+        let mut modified = Vec::new();
+
+        for path in candidates {
+            let current = read_entry(&path);
+
+            match (self.entries_by_path.get(&path), current) {
+                (None, Some(current)) => {
+                    created.push((path.clone(), current.file_type.clone()));
+                    self.entries_by_path.insert(path, current);
+                }
+                (Some(cached), None) => {
+                    removed.push((path.clone(), cached.file_type.clone()));
+                    self.entries_by_path.remove(&path);
+                }
+                (Some(cached), Some(current)) if cached.file_id != current.file_id => {
+                    removed.push((path.clone(), cached.file_type.clone()));
+                    created.push((path.clone(), current.file_type.clone()));
+                    self.entries_by_path.insert(path, current);
+                }
+                (Some(cached), Some(current)) if current.is_modified(cached) => {
+                    modified.push(path.clone());
+                    self.entries_by_path.insert(path, current);
+                }
+                _ => {}
+            }
+        }
+
+        // children are removed before their parents and created after them
+        removed.sort_by(|a, b| b.0.cmp(&a.0));
+        created.sort_by(|a, b| a.0.cmp(&b.0));
+        modified.sort();
+
+        let removed = removed
+            .into_iter()
+            .map(|(path, file_type)| NotifyEvent::new(EventKind::Remove(remove_kind(&file_type))).add_path(path));
+        let created = created.into_iter().map(|(path, file_type)| {
+            let kind = match file_type {
+                ObjectType::Dir => CreateKind::Folder,
+                ObjectType::File => CreateKind::File,
+                _ => CreateKind::Other,
+            };
+
+            NotifyEvent::new(EventKind::Create(kind)).add_path(path)
+        });
+        let modified = modified
+            .into_iter()
+            .map(|path| NotifyEvent::new(EventKind::Modify(ModifyKind::Data(DataChange::Content))).add_path(path));
+
+        removed.chain(created).chain(modified).collect()
+    }
+
+    fn is_recursive(&self, path: &Path) -> bool {
+        self.roots
             .iter()
             .find_map(|(root, recursive_mode)| {
                 if path.starts_with(root) {
@@ -96,38 +607,217 @@ impl FileIdCache for FileCache {
                     None
                 }
             })
-            .unwrap_or_default();
-
-        for (path, file_id) in WalkDir::new(path)
-            .follow_links(true)
-            .max_depth(Self::dir_scan_depth(is_recursive))
-            .into_iter()
-            .filter_map(|entry| {
-                let path = entry.ok()?.into_path();
-                let file_id = get_file_id(&path).ok()?;
+            .unwrap_or_default()
+    }
 
-                Some((path, file_id))
-            })
-        {
-            self.file_ids_by_path.insert(path, file_id);
+    fn dir_scan_depth(is_recursive: bool) -> usize {
+        if is_recursive {
+            return usize::MAX;
         }
+
+        1
+    }
+}
+
+impl FileIdCache for FileCache {
+    fn get_file_id(&self, path: &Path) -> Option<&FileId> {
+        self.entries_by_path.get(path).map(|entry| &entry.file_id)
+    }
+
+    fn add_path(&mut self, path: &Path) {
+        let is_recursive = self.is_recursive(path);
+
+        self.entries_by_path
+            .extend(walk(path, is_recursive, self.filter.as_ref()));
     }
 
     fn remove_path(&mut self, path: &Path) {
-        self.file_ids_by_path.retain(|p, _| !p.starts_with(path));
+        let removed: Vec<PathBuf> = self.subtree(path).cloned().collect();
+
+        for path in removed {
+            self.entries_by_path.remove(&path);
+        }
     }
 
     fn rescan(&mut self) {
-        for (root, _) in self.roots.clone() {
-            self.add_path(&root);
+        let mut rescan = self.start_rescan(None);
+        rescan.walk();
+
+        self.finish_rescan(rescan);
+    }
+}
+
+/// Walk the path (and its children if `is_recursive`), skipping paths matching the filter.
+fn walk(path: &Path, is_recursive: bool, filter: Option<&EventFilter>) -> impl Iterator<Item = (PathBuf, FileEntry)> {
+    WalkDir::new(path)
+        .follow_links(true)
+        .max_depth(FileCache::dir_scan_depth(is_recursive))
+        .into_iter()
+        // filtered directories are not walked into
+        .filter_entry(move |entry| filter.is_none_or(|f| !f.should_filter_path(entry.path())))
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let metadata = entry.metadata().ok()?;
+            let path = entry.into_path();
+            let file_id = get_file_id(&path).ok()?;
+
+            Some((path, FileEntry::new(file_id, &metadata)))
+        })
+}
+
+fn remove_kind(file_type: &ObjectType) -> RemoveKind {
+    match file_type {
+        ObjectType::Dir => RemoveKind::Folder,
+        ObjectType::File => RemoveKind::File,
+        _ => RemoveKind::Other,
+    }
+}
+
+/// Read what is currently on disk at the path.
+fn read_entry(path: &Path) -> Option<FileEntry> {
+    let metadata = fs::metadata(path).ok()?;
+    let file_id = get_file_id(path).ok()?;
+
+    Some(FileEntry::new(file_id, &metadata))
+}
+
+impl FileMetadataCache for FileCache {
+    fn get_entry(&self, path: &Path) -> Option<&FileEntry> {
+        self.entries_by_path.get(path)
+    }
+
+    fn children(&self, path: &Path) -> Vec<PathBuf> {
+        self.subtree(path)
+            .filter(|p| p.parent() == Some(path))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Returns events that turn the `previous` entries into the `current` ones.
+fn diff(previous: &BTreeMap<PathBuf, FileEntry>, current: &BTreeMap<PathBuf, FileEntry>) -> Vec<EventType> {
+    // a path that is still there, but holds another file, was replaced
+    let mut gone: Vec<&PathBuf> = previous
+        .iter()
+        .filter(|(path, entry)| current.get(*path).is_none_or(|e| e.file_id != entry.file_id))
+        .map(|(path, _)| path)
+        .collect();
+    // entries are sorted, so parents go before their children
+    // and directory renames are known by the time their children are checked
+    let appeared: Vec<&PathBuf> = current
+        .iter()
+        .filter(|(path, entry)| previous.get(*path).is_none_or(|e| e.file_id != entry.file_id))
+        .map(|(path, _)| path)
+        .collect();
+
+    // hard links share a file ID, so an ID may have been at several paths
+    let mut gone_by_id: HashMap<&FileId, Vec<&PathBuf>> = HashMap::new();
+    for path in &gone {
+        gone_by_id.entry(&previous[*path].file_id).or_default().push(*path);
+    }
+
+    let mut renamed: HashSet<&PathBuf> = HashSet::new();
+    let mut renamed_dirs: HashMap<&Path, &Path> = HashMap::new();
+    let mut moves = Vec::new();
+    let mut modified: Vec<&PathBuf> = Vec::new();
+
+    for path in appeared {
+        let entry = &current[path];
+
+        // a link moved along with its renamed parent is paired with its old location,
+        // so links don't get each other's paths
+        let old_path = gone_by_id
+            .get_mut(&entry.file_id)
+            .filter(|paths| !paths.is_empty())
+            .map(|paths| {
+                let index = paths
+                    .iter()
+                    .position(|old_path| moved_with_parent(path, old_path, &renamed_dirs))
+                    .unwrap_or(0);
+
+                paths.remove(index)
+            });
+
+        let Some(old_path) = old_path else {
+            moves.push(EventType::Create(CreateEvent::new(
+                path.clone(),
+                entry.file_type.clone(),
+            )));
+            continue;
+        };
+
+        renamed.insert(old_path);
+
+        if entry.is_modified(&previous[old_path]) {
+            modified.push(path);
         }
+
+        let is_dir = entry.file_type == ObjectType::Dir;
+
+        if is_dir {
+            renamed_dirs.insert(path, old_path);
+        }
+
+        // children of renamed directories are covered by the directory rename
+        if !moved_with_parent(path, old_path, &renamed_dirs) {
+            moves.push(EventType::Rename(RenameEvent::new(
+                old_path.clone(),
+                path.clone(),
+                is_dir,
+                Vec::new(),
+            )));
+        }
+    }
+
+    // deletes go first, so replaced paths are freed up before they're created or renamed to,
+    // and children go before their parents
+    gone.retain(|path| !renamed.contains(path));
+    gone.sort_by(|a, b| b.cmp(a));
+
+    let mut events: Vec<EventType> = gone
+        .into_iter()
+        .map(|path| EventType::Delete(DeleteEvent::new(path.clone(), previous[path].file_type.clone())))
+        .collect();
+
+    events.extend(moves);
+
+    modified.extend(
+        current
+            .iter()
+            .filter(|(path, entry)| {
+                previous
+                    .get(*path)
+                    .is_some_and(|e| e.file_id == entry.file_id && entry.is_modified(e))
+            })
+            .map(|(path, _)| path),
+    );
+    modified.sort();
+
+    for path in modified {
+        events.push(EventType::ModifyData(ModifyDataEvent::new(
+            path.clone(),
+            DataType::Content,
+        )));
     }
+
+    events
+}
+
+/// Whether `path` was at `old_path` before, because one of its parent directories was renamed.
+fn moved_with_parent(path: &Path, old_path: &Path, renamed_dirs: &HashMap<&Path, &Path>) -> bool {
+    path.ancestors().skip(1).any(|new_dir| {
+        renamed_dirs.get(new_dir).is_some_and(|old_dir| {
+            path.strip_prefix(new_dir)
+                .is_ok_and(|relative| old_dir.join(relative) == old_path)
+        })
+    })
 }
 
 /// An implementation of the `FileIdCache` trait that doesn't hold any data.
 ///
 /// This pseudo cache can be used to disable the file tracking using file system IDs.
 #[allow(dead_code)]
+#[derive(Debug)]
 pub struct NoCache;
 
 impl FileIdCache for NoCache {
@@ -141,3 +831,447 @@ impl FileIdCache for NoCache {
 
     fn rescan(&mut self) {}
 }
+
+impl FileMetadataCache for NoCache {
+    fn get_entry(&self, _path: &Path) -> Option<&FileEntry> {
+        None
+    }
+
+    fn children(&self, _path: &Path) -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    fn rename(mode: RenameMode, paths: &[&Path]) -> NotifyEvent {
+        let mut event = NotifyEvent::new(EventKind::Modify(ModifyKind::Name(mode)));
+
+        for path in paths {
+            event = event.add_path(path.to_path_buf());
+        }
+
+        event
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("notifykit-file-cache-{name}-{}", std::process::id()));
+
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn test_dir_rename_moves_children() {
+        let root = temp_dir("rename");
+        let old_dir = root.join("old");
+        let new_dir = root.join("new");
+
+        fs::create_dir_all(old_dir.join("nested")).unwrap();
+        fs::write(old_dir.join("a.txt"), "a").unwrap();
+        fs::write(old_dir.join("nested/b.txt"), "b").unwrap();
+
+        let mut cache = FileCache::new();
+        cache.add_root(&root, RecursiveMode::Recursive);
+
+        fs::rename(&old_dir, &new_dir).unwrap();
+        cache.handle_event(&rename(RenameMode::From, &[&old_dir]));
+        cache.handle_event(&rename(RenameMode::To, &[&new_dir]));
+        cache.handle_event(&rename(RenameMode::Both, &[&old_dir, &new_dir]));
+
+        assert!(cache.get_file_id(&old_dir.join("a.txt")).is_none());
+        assert_eq!(
+            cache.renamed_children(&old_dir, &new_dir),
+            vec![
+                (old_dir.join("a.txt"), new_dir.join("a.txt")),
+                (old_dir.join("nested"), new_dir.join("nested")),
+                (old_dir.join("nested/b.txt"), new_dir.join("nested/b.txt")),
+            ]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_moved_out_is_removed() {
+        let root = temp_dir("moved-out");
+        let file = root.join("a.txt");
+        fs::write(&file, "a").unwrap();
+
+        let mut cache = FileCache::new();
+        cache.add_root(&root, RecursiveMode::Recursive);
+
+        cache.handle_event(&rename(RenameMode::From, &[&file]));
+        cache.handle_event(&NotifyEvent::new(EventKind::Create(CreateKind::File)).add_path(root.join("b.txt")));
+
+        assert!(cache.get_file_id(&file).is_none());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    fn scan(root: &Path) -> FileCache {
+        let mut cache = FileCache::new();
+        cache.add_root(root, RecursiveMode::Recursive);
+
+        cache
+    }
+
+    fn describe(events: &[EventType], root: &Path) -> Vec<String> {
+        let relative = |path: &Path| path.strip_prefix(root).unwrap().display().to_string();
+
+        events
+            .iter()
+            .map(|e| match e {
+                EventType::Rename(e) => format!("rename {} {}", relative(&e.old_path), relative(&e.new_path)),
+                _ => format!("{} {}", e.kind(), relative(e.path().unwrap())),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_since_saved_snapshot() {
+        let root = temp_dir("diff");
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("docs/a.md"), "a").unwrap();
+        fs::write(root.join("kept.txt"), "kept").unwrap();
+        fs::write(root.join("changed.txt"), "v1").unwrap();
+        fs::write(root.join("removed.txt"), "removed").unwrap();
+        fs::write(root.join("moved.txt"), "moved").unwrap();
+
+        let snapshot_path = std::env::temp_dir().join(format!("notifykit-snapshot-{}.json", std::process::id()));
+        scan(&root).save(&snapshot_path).unwrap();
+
+        fs::rename(root.join("docs"), root.join("guides")).unwrap();
+        fs::write(root.join("changed.txt"), "version 2").unwrap();
+        // created before the removal, so the file system doesn't reuse the removed file ID
+        fs::write(root.join("new.txt"), "new").unwrap();
+        fs::remove_file(root.join("removed.txt")).unwrap();
+        fs::rename(root.join("moved.txt"), root.join("renamed.txt")).unwrap();
+
+        let snapshot = FileCache::load(&snapshot_path).unwrap();
+        let events = scan(&root).diff_since(&snapshot);
+
+        assert_eq!(
+            describe(&events, &root),
+            vec![
+                "delete removed.txt",
+                "rename docs guides",
+                "create new.txt",
+                "rename moved.txt renamed.txt",
+                "modify_data changed.txt",
+            ]
+        );
+
+        fs::remove_file(&snapshot_path).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_diff_since_hard_links() {
+        let root = temp_dir("hard-links");
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("docs/a.md"), "a").unwrap();
+        fs::hard_link(root.join("docs/a.md"), root.join("docs/b.md")).unwrap();
+        fs::hard_link(root.join("docs/a.md"), root.join("c.md")).unwrap();
+
+        let snapshot = scan(&root);
+
+        // every link keeps its own old path instead of taking the one of another link
+        fs::rename(root.join("docs"), root.join("guides")).unwrap();
+        fs::remove_file(root.join("c.md")).unwrap();
+
+        assert_eq!(
+            describe(&scan(&root).diff_since(&snapshot), &root),
+            vec!["delete c.md", "rename docs guides"]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_save_non_unicode_path() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let root = temp_dir("non-unicode");
+        let name = std::ffi::OsStr::from_bytes(b"caf\xe9.txt");
+        fs::write(root.join("plain.txt"), "plain").unwrap();
+
+        // some file systems (e.g. on macOS) only allow valid UTF-8 names
+        if fs::write(root.join(name), "latin-1").is_err() {
+            return;
+        }
+
+        let snapshot_path = std::env::temp_dir().join(format!("notifykit-snapshot-raw-{}.json", std::process::id()));
+        let cache = scan(&root);
+        cache.save(&snapshot_path).unwrap();
+
+        let snapshot = FileCache::load(&snapshot_path).unwrap();
+
+        assert_eq!(snapshot.len(), cache.len());
+        assert!(snapshot.get_entry(&root.join(name)).is_some());
+        assert!(cache.diff_since(&snapshot).is_empty());
+
+        fs::remove_file(&snapshot_path).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_diff_since_replaced_file() {
+        let root = temp_dir("replaced");
+        fs::write(root.join("a.txt"), "a").unwrap();
+
+        let snapshot = scan(&root);
+
+        fs::write(root.join("b.txt"), "b").unwrap();
+        fs::rename(root.join("b.txt"), root.join("a.txt")).unwrap();
+
+        let events = scan(&root).diff_since(&snapshot);
+
+        assert_eq!(describe(&events, &root), vec!["delete a.txt", "create a.txt"]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_metadata_queries() {
+        let root = temp_dir("metadata");
+        fs::create_dir_all(root.join("src/nested")).unwrap();
+        fs::write(root.join("src/lib.rs"), "lib").unwrap();
+        fs::write(root.join("src/nested/mod.rs"), "mod").unwrap();
+
+        let cache = scan(&root);
+
+        assert_eq!(cache.is_dir(&root.join("src")), Some(true));
+        assert_eq!(cache.is_dir(&root.join("src/lib.rs")), Some(false));
+        assert_eq!(cache.is_dir(&root.join("missing")), None);
+        assert_eq!(cache.get_entry(&root.join("src/lib.rs")).unwrap().size, 3);
+        assert_eq!(
+            cache.children(&root.join("src")),
+            vec![root.join("src/lib.rs"), root.join("src/nested")]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_subtree_skips_siblings_with_same_prefix() {
+        let root = temp_dir("subtree");
+        fs::create_dir_all(root.join("dir/nested")).unwrap();
+        fs::write(root.join("dir/a.txt"), "a").unwrap();
+        fs::create_dir_all(root.join("dir b")).unwrap();
+        fs::write(root.join("dir-c.txt"), "c").unwrap();
+        fs::write(root.join("dir.txt"), "d").unwrap();
+
+        let mut cache = scan(&root);
+
+        assert_eq!(
+            cache.descendants(&root.join("dir")),
+            vec![root.join("dir/a.txt"), root.join("dir/nested")]
+        );
+        assert_eq!(
+            cache.children(&root),
+            vec![
+                root.join("dir"),
+                root.join("dir b"),
+                root.join("dir-c.txt"),
+                root.join("dir.txt")
+            ]
+        );
+
+        cache.remove_path(&root.join("dir"));
+
+        assert_eq!(cache.len(), 4);
+        assert!(cache.get_file_id(&root.join("dir/nested")).is_none());
+        assert!(cache.get_file_id(&root.join("dir b")).is_some());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_classify_removed_dir() {
+        let root = temp_dir("classify");
+        let dir = root.join("dir");
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut cache = scan(&root);
+        fs::remove_dir(&dir).unwrap();
+
+        let event = cache.classify(NotifyEvent::new(EventKind::Remove(RemoveKind::Any)).add_path(dir.clone()));
+        cache.handle_event(&event);
+
+        assert_eq!(event.kind, EventKind::Remove(RemoveKind::Folder));
+        assert_eq!(cache.is_dir(&dir), None);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_modify_refreshes_metadata() {
+        let root = temp_dir("refresh");
+        let file = root.join("a.txt");
+        fs::write(&file, "a").unwrap();
+
+        let mut cache = scan(&root);
+        let stale =
+            NotifyEvent::new(EventKind::Modify(ModifyKind::Metadata(MetadataKind::WriteTime))).add_path(file.clone());
+
+        assert!(cache.is_noise(&stale));
+
+        fs::write(&file, "version 2").unwrap();
+        assert!(!cache.is_noise(&stale));
+
+        cache.handle_event(
+            &NotifyEvent::new(EventKind::Modify(ModifyKind::Data(DataChange::Content))).add_path(file.clone()),
+        );
+
+        assert_eq!(cache.get_entry(&file).unwrap().size, 9);
+        assert!(cache.is_noise(&stale));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    fn describe_raw(events: &[NotifyEvent], root: &Path) -> Vec<String> {
+        events
+            .iter()
+            .map(|e| format!("{:?} {}", e.kind, e.paths[0].strip_prefix(root).unwrap().display()))
+            .collect()
+    }
+
+    #[test]
+    fn test_rescan_reports_missed_changes() {
+        let root = temp_dir("rescan");
+        fs::create_dir_all(root.join("gone/nested")).unwrap();
+        fs::write(root.join("gone/nested/a.txt"), "a").unwrap();
+        fs::write(root.join("changed.txt"), "v1").unwrap();
+
+        let mut cache = scan(&root);
+
+        fs::remove_dir_all(root.join("gone")).unwrap();
+        fs::create_dir_all(root.join("new")).unwrap();
+        fs::write(root.join("new/b.txt"), "b").unwrap();
+        fs::write(root.join("changed.txt"), "version 2").unwrap();
+
+        let mut rescan = cache.start_rescan(None);
+        rescan.walk();
+        let events = cache.finish_rescan(rescan);
+
+        assert_eq!(
+            describe_raw(&events, &root),
+            vec![
+                "Remove(File) gone/nested/a.txt",
+                "Remove(Folder) gone/nested",
+                "Remove(Folder) gone",
+                "Create(Folder) new",
+                "Create(File) new/b.txt",
+                "Modify(Data(Content)) changed.txt",
+            ]
+        );
+        assert!(scan(&root).diff_since(&cache).is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_rescan_subtree() {
+        let root = temp_dir("rescan-subtree");
+        fs::create_dir_all(root.join("overflowed")).unwrap();
+        fs::write(root.join("a.txt"), "a").unwrap();
+
+        let mut cache = scan(&root);
+
+        fs::remove_file(root.join("a.txt")).unwrap();
+        fs::write(root.join("overflowed/b.txt"), "b").unwrap();
+
+        let mut rescan = cache.start_rescan(Some(&root.join("overflowed")));
+        rescan.walk();
+
+        assert_eq!(
+            describe_raw(&cache.finish_rescan(rescan), &root),
+            vec!["Create(File) overflowed/b.txt"]
+        );
+        // changes outside of the subtree are left for another rescan
+        assert!(cache.get_file_id(&root.join("a.txt")).is_some());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_rescan_merges_scopes() {
+        let root = temp_dir("rescan-merge");
+        fs::create_dir_all(root.join("a/nested")).unwrap();
+        fs::create_dir_all(root.join("b")).unwrap();
+
+        let mut cache = scan(&root);
+
+        fs::write(root.join("a/nested/1.txt"), "1").unwrap();
+        fs::write(root.join("b/2.txt"), "2").unwrap();
+
+        let mut rescan = cache.start_rescan(Some(&root.join("a/nested")));
+        rescan.merge(cache.start_rescan(Some(&root.join("b"))));
+        rescan.merge(cache.start_rescan(Some(&root.join("a"))));
+        rescan.merge(cache.start_rescan(Some(&root.join("a/nested"))));
+
+        assert_eq!(rescan.scope, vec![(root.join("b"), true), (root.join("a"), true)]);
+
+        rescan.walk();
+
+        assert_eq!(
+            describe_raw(&cache.finish_rescan(rescan), &root),
+            vec!["Create(File) a/nested/1.txt", "Create(File) b/2.txt"]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_rescan_skips_changes_made_after_walk() {
+        let root = temp_dir("rescan-race");
+        let mut cache = scan(&root);
+
+        let mut rescan = cache.start_rescan(None);
+        rescan.walk();
+
+        // the file is reported by the back-end after the walk
+        fs::write(root.join("late.txt"), "late").unwrap();
+        cache.handle_event(&NotifyEvent::new(EventKind::Create(CreateKind::File)).add_path(root.join("late.txt")));
+
+        assert_eq!(cache.finish_rescan(rescan), Vec::new());
+        assert!(cache.get_file_id(&root.join("late.txt")).is_some());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_removed_children_depth_first() {
+        let root = temp_dir("removed-children");
+        let dir = root.join("dir");
+        fs::create_dir_all(dir.join("a/nested")).unwrap();
+        fs::write(dir.join("a/nested/1.txt"), "1").unwrap();
+        fs::write(dir.join("b.txt"), "b").unwrap();
+
+        let cache = scan(&root);
+        let removed = NotifyEvent::new(EventKind::Remove(RemoveKind::Folder)).add_path(dir.clone());
+
+        assert_eq!(
+            describe_raw(&cache.removed_children(&removed), &dir),
+            vec![
+                "Remove(File) b.txt",
+                "Remove(File) a/nested/1.txt",
+                "Remove(Folder) a/nested",
+                "Remove(Folder) a",
+            ]
+        );
+
+        let removed_file = NotifyEvent::new(EventKind::Remove(RemoveKind::File)).add_path(dir.join("b.txt"));
+        assert!(cache.removed_children(&removed_file).is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -5,8 +5,8 @@ use regex::Regex;
 
 use crate::events::EventType;
 
-#[derive(Debug)]
-pub(crate) struct EventFilter {
+#[derive(Debug, Clone)]
+pub struct EventFilter {
     ignore_dirs: HashSet<String>,
     ignore_patterns: Vec<Regex>,
     ignore_paths: Vec<String>,
@@ -44,7 +44,7 @@ impl EventFilter {
         }
     }
 
-    fn should_filter_path(&self, path: &Path) -> bool {
+    pub fn should_filter_path(&self, path: &Path) -> bool {
         // Check if any path component matches ignore_dirs
         if !self.ignore_dirs.is_empty() {
             for component in path.components() {
@@ -149,6 +149,8 @@ mod tests {
         let event = EventType::Rename(RenameEvent::new(
             PathBuf::from("/home/user/proj/__pycache__/a"),
             PathBuf::from("/home/user/proj/.venv/b"),
+            false,
+            Vec::new(),
         ));
         assert!(f.should_filter(&event));
     }
@@ -160,6 +162,8 @@ mod tests {
         let event = EventType::Rename(RenameEvent::new(
             PathBuf::from("/home/user/proj/__pycache__/a"),
             PathBuf::from("/home/user/proj/real_file"),
+            false,
+            Vec::new(),
         ));
         assert!(!f.should_filter(&event));
     }
@@ -1,10 +1,14 @@
 use std::collections::{HashMap, VecDeque};
+use std::fmt::Debug;
 use std::ops::{Deref, DerefMut};
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use std::time::Instant;
 
 use crate::file_cache::FileIdCache;
+use crate::suppress::{SuppressingProcessor, Suppressor};
 use file_id::FileId;
 use notify::event::{ModifyKind, RemoveKind, RenameMode};
 use notify::{Error as NotifyError, Event as NotifyEvent, EventKind};
@@ -86,13 +90,123 @@ impl FileEventQueue {
     }
 }
 
-pub(crate) trait EventProcessor {
-    fn get_events(&mut self) -> Vec<RawEvent>;
+/// Buffers events of the backend until they're ready for delivery.
+///
+/// Processors take the time from the `_at()` methods rather than the clock,
+/// so recorded events can be replayed on their own timeline (see `Replay`).
+pub trait EventProcessor: Debug + Send {
+    fn get_events(&mut self) -> Vec<RawEvent> {
+        self.get_events_at(Instant::now())
+    }
+    /// Returns events that are ready for delivery at the given time
+    fn get_events_at(&mut self, now: Instant) -> Vec<RawEvent>;
+    /// Returns all pending events, no matter how long they have been buffered
+    fn flush_events(&mut self) -> Vec<RawEvent>;
     fn get_errors(&mut self) -> Vec<NotifyError>;
-    fn add_event(&mut self, event: NotifyEvent);
+    fn add_event(&mut self, event: NotifyEvent) {
+        self.add_event_at(event, Instant::now());
+    }
+    /// Add an event that has arrived at the given time
+    fn add_event_at(&mut self, event: NotifyEvent, time: Instant);
     fn add_error(&mut self, error: NotifyError);
 }
 
+/// An event processor shared between the backend callback and the drain loop
+pub type SharedProcessor = Arc<Mutex<dyn EventProcessor>>;
+
+/// Defines how buffered events are released for delivery
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum DeliveryMode {
+    /// Release each event once it's been buffered for the buffering time
+    #[default]
+    Delay,
+    /// Release events of a path once no new events have arrived for it during the buffering time
+    Debounce,
+    /// Release the first event of a path right away and drop its further events during the buffering time
+    Leading,
+    /// Release all pending events at most once per buffering time
+    Throttle,
+}
+
+impl FromStr for DeliveryMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "delay" => Ok(Self::Delay),
+            "debounce" => Ok(Self::Debounce),
+            "leading" => Ok(Self::Leading),
+            "throttle" => Ok(Self::Throttle),
+            _ => Err(format!(
+                "unknown delivery mode: {s} (expected one of delay, debounce, leading, throttle)"
+            )),
+        }
+    }
+}
+
+/// Settings of the processor that buffers events before delivery
+#[derive(Debug, Copy, Clone)]
+pub struct DeliveryOptions {
+    pub mode: DeliveryMode,
+    pub buffering_time: Duration,
+    /// The longest time events can be held back in the `debounce` mode
+    pub max_wait: Option<Duration>,
+}
+
+impl Default for DeliveryOptions {
+    fn default() -> Self {
+        Self {
+            mode: DeliveryMode::Delay,
+            buffering_time: Duration::from_millis(200),
+            max_wait: None,
+        }
+    }
+}
+
+impl DeliveryOptions {
+    pub fn processor(&self) -> SharedProcessor {
+        self.build_processor(None)
+    }
+
+    /// The processor of the delivery mode that drops suppressed events as they arrive (see `SuppressingProcessor`).
+    pub(crate) fn suppressing_processor(&self, suppressor: Arc<Mutex<Suppressor>>) -> SharedProcessor {
+        self.build_processor(Some(suppressor))
+    }
+
+    fn build_processor(&self, suppressor: Option<Arc<Mutex<Suppressor>>>) -> SharedProcessor {
+        let buffering_time = self.buffering_time;
+
+        match self.mode {
+            DeliveryMode::Delay => shared(BatchProcessor::new(buffering_time), suppressor),
+            DeliveryMode::Debounce => shared(DebounceProcessor::new(buffering_time, self.max_wait), suppressor),
+            DeliveryMode::Leading => shared(LeadingEdgeProcessor::new(buffering_time), suppressor),
+            DeliveryMode::Throttle => shared(ThrottleProcessor::new(buffering_time), suppressor),
+        }
+    }
+
+    /// Whether new events may be ready for delivery as soon as they arrive,
+    /// so the drain loop should not wait for its next tick
+    pub fn is_immediate(&self) -> bool {
+        matches!(self.mode, DeliveryMode::Leading | DeliveryMode::Throttle)
+    }
+
+    /// How long events may stay in the processor (unless it's debouncing without a ceiling)
+    pub fn max_buffering_time(&self) -> Duration {
+        self.max_wait.unwrap_or_default().max(self.buffering_time)
+    }
+}
+
+/// Wrap the processor into the stages that come before buffering: rename pairing and then suppression,
+/// so renames are suppressed with both of their paths.
+fn shared<P: EventProcessor + 'static>(processor: P, suppressor: Option<Arc<Mutex<Suppressor>>>) -> SharedProcessor {
+    match suppressor {
+        Some(suppressor) => Arc::new(Mutex::new(RenamePairing::new(SuppressingProcessor::new(
+            processor, suppressor,
+        )))),
+        None => Arc::new(Mutex::new(RenamePairing::new(processor))),
+    }
+}
+
 #[derive(Debug)]
 pub struct CrossPlatformEventProcessor<T> {
     events_by_file: HashMap<PathBuf, FileEventQueue>,
@@ -117,8 +231,7 @@ impl<T: FileIdCache> CrossPlatformEventProcessor<T> {
         }
     }
 
-    fn handle_rename_from(&mut self, event: NotifyEvent) {
-        let time = Instant::now();
+    fn handle_rename_from(&mut self, event: NotifyEvent, time: Instant) {
         let path = &event.paths[0];
 
         // store event
@@ -130,7 +243,7 @@ impl<T: FileIdCache> CrossPlatformEventProcessor<T> {
         self.push_event(event, time);
     }
 
-    fn handle_rename_to(&mut self, event: NotifyEvent) {
+    fn handle_rename_to(&mut self, event: NotifyEvent, time: Instant) {
         self.file_cache.add_path(&event.paths[0]);
 
         let trackers_match = self
@@ -159,7 +272,7 @@ impl<T: FileIdCache> CrossPlatformEventProcessor<T> {
             self.push_rename_event(path, event, time);
         } else {
             // move in
-            self.push_event(event, Instant::now());
+            self.push_event(event, time);
         }
 
         self.rename_event = None;
@@ -281,16 +394,14 @@ impl<T: FileIdCache> CrossPlatformEventProcessor<T> {
             );
         }
     }
-}
 
-impl<T: FileIdCache> EventProcessor for CrossPlatformEventProcessor<T> {
-    fn get_events(&mut self) -> Vec<RawEvent> {
-        let now = Instant::now();
+    /// Returns events that have been buffered for at least `buffering_time` by the given time
+    fn take_events(&mut self, buffering_time: Duration, now: Instant) -> Vec<RawEvent> {
         let mut events_to_return = Vec::with_capacity(self.events_by_file.len());
         let mut remaining_events = HashMap::with_capacity(self.events_by_file.len());
 
         if let Some(rescan_event) = self.rescan_event.take() {
-            if now.saturating_duration_since(rescan_event.time) >= self.buffering_time {
+            if now.saturating_duration_since(rescan_event.time) >= buffering_time {
                 // log::trace!("debounced event: {rescan_event:?}");
 
                 events_to_return.push(rescan_event);
@@ -304,7 +415,7 @@ impl<T: FileIdCache> EventProcessor for CrossPlatformEventProcessor<T> {
             let mut kind_index = HashMap::new();
 
             while let Some(event) = events.events.pop_front() {
-                if now.saturating_duration_since(event.time) >= self.buffering_time {
+                if now.saturating_duration_since(event.time) >= buffering_time {
                     // remove previous event of the same kind
                     if let Some(idx) = kind_index.get(&event.kind).copied() {
                         events_to_return.remove(idx);
@@ -344,6 +455,16 @@ impl<T: FileIdCache> EventProcessor for CrossPlatformEventProcessor<T> {
 
         events_to_return
     }
+}
+
+impl<T: FileIdCache + Debug + Send> EventProcessor for CrossPlatformEventProcessor<T> {
+    fn get_events_at(&mut self, now: Instant) -> Vec<RawEvent> {
+        self.take_events(self.buffering_time, now)
+    }
+
+    fn flush_events(&mut self) -> Vec<RawEvent> {
+        self.take_events(Duration::ZERO, Instant::now())
+    }
 
     /// Returns all currently stored errors
     fn get_errors(&mut self) -> Vec<NotifyError> {
@@ -351,12 +472,12 @@ impl<T: FileIdCache> EventProcessor for CrossPlatformEventProcessor<T> {
     }
 
     /// Add new event to debouncer cache
-    fn add_event(&mut self, event: NotifyEvent) {
+    fn add_event_at(&mut self, event: NotifyEvent, time: Instant) {
         // log::trace!("raw event: {event:?}");
 
         if event.need_rescan() {
             self.file_cache.rescan();
-            self.rescan_event = Some(event.into());
+            self.rescan_event = Some(RawEvent::new(event, time));
             return;
         }
 
@@ -366,22 +487,22 @@ impl<T: FileIdCache> EventProcessor for CrossPlatformEventProcessor<T> {
             EventKind::Create(_) => {
                 self.file_cache.add_path(path);
 
-                self.push_event(event, Instant::now());
+                self.push_event(event, time);
             }
             EventKind::Modify(ModifyKind::Name(rename_mode)) => {
                 match rename_mode {
                     RenameMode::Any => {
                         if event.paths[0].exists() {
-                            self.handle_rename_to(event);
+                            self.handle_rename_to(event, time);
                         } else {
-                            self.handle_rename_from(event);
+                            self.handle_rename_from(event, time);
                         }
                     }
                     RenameMode::To => {
-                        self.handle_rename_to(event);
+                        self.handle_rename_to(event, time);
                     }
                     RenameMode::From => {
-                        self.handle_rename_from(event);
+                        self.handle_rename_from(event, time);
                     }
                     RenameMode::Both => {
                         // ignore and handle `To` and `From` events instead
@@ -392,7 +513,7 @@ impl<T: FileIdCache> EventProcessor for CrossPlatformEventProcessor<T> {
                 }
             }
             EventKind::Remove(_) => {
-                self.push_remove_event(event, Instant::now());
+                self.push_remove_event(event, time);
             }
             EventKind::Other => {
                 // ignore meta events
@@ -402,7 +523,7 @@ impl<T: FileIdCache> EventProcessor for CrossPlatformEventProcessor<T> {
                     self.file_cache.add_path(path);
                 }
 
-                self.push_event(event, Instant::now());
+                self.push_event(event, time);
             }
         }
     }
@@ -433,13 +554,11 @@ impl BatchProcessor {
 }
 
 impl EventProcessor for BatchProcessor {
-    fn get_events(&mut self) -> Vec<RawEvent> {
+    fn get_events_at(&mut self, now: Instant) -> Vec<RawEvent> {
         if self.events.is_empty() {
             return Vec::new();
         }
 
-        let now = Instant::now();
-
         // Assuming expired items are contiguous and at the beginning of the vector
         let first_non_expired_index = self
             .events
@@ -450,6 +569,10 @@ impl EventProcessor for BatchProcessor {
         self.events.drain(0..first_non_expired_index).collect()
     }
 
+    fn flush_events(&mut self) -> Vec<RawEvent> {
+        std::mem::take(&mut self.events)
+    }
+
     fn get_errors(&mut self) -> Vec<NotifyError> {
         if self.errors.is_empty() {
             return Vec::new();
@@ -458,11 +581,600 @@ impl EventProcessor for BatchProcessor {
         std::mem::take(&mut self.errors)
     }
 
-    fn add_event(&mut self, event: NotifyEvent) {
-        self.events.push(RawEvent::new(event, Instant::now()));
+    fn add_event_at(&mut self, event: NotifyEvent, time: Instant) {
+        self.events.push(RawEvent::new(event, time));
+    }
+
+    fn add_error(&mut self, error: NotifyError) {
+        self.errors.push(error);
+    }
+}
+
+#[derive(Debug)]
+struct PathEvents {
+    events: Vec<RawEvent>,
+    first_seen: Instant,
+    last_seen: Instant,
+}
+
+/// A trailing-edge debouncing event processor.
+///
+/// Events are held per path until the path has been quiet for the given period,
+/// so a file that keeps changing produces one batch once the changes stop.
+/// `max_wait` puts a ceiling on how long events of a continuously changing path can be held back.
+#[derive(Debug)]
+pub struct DebounceProcessor {
+    quiet_period: Duration,
+    max_wait: Option<Duration>,
+    events_by_path: HashMap<PathBuf, PathEvents>,
+    errors: Vec<NotifyError>,
+}
+
+impl DebounceProcessor {
+    pub fn new(quiet_period: Duration, max_wait: Option<Duration>) -> Self {
+        Self {
+            quiet_period,
+            max_wait,
+            events_by_path: HashMap::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn release_events(&mut self, now: Instant) -> Vec<RawEvent> {
+        let mut events = Vec::new();
+
+        self.events_by_path.retain(|_, path_events| {
+            let quiet = now.saturating_duration_since(path_events.last_seen) >= self.quiet_period;
+            let overdue = self
+                .max_wait
+                .is_some_and(|max_wait| now.saturating_duration_since(path_events.first_seen) >= max_wait);
+
+            if quiet || overdue {
+                events.append(&mut path_events.events);
+                return false;
+            }
+
+            true
+        });
+
+        events.sort_by_key(|event| event.time);
+
+        events
+    }
+}
+
+impl EventProcessor for DebounceProcessor {
+    fn get_events_at(&mut self, now: Instant) -> Vec<RawEvent> {
+        if self.events_by_path.is_empty() {
+            return Vec::new();
+        }
+
+        self.release_events(now)
+    }
+
+    fn flush_events(&mut self) -> Vec<RawEvent> {
+        let mut events: Vec<RawEvent> = self.events_by_path.drain().flat_map(|(_, e)| e.events).collect();
+
+        events.sort_by_key(|event| event.time);
+
+        events
+    }
+
+    fn get_errors(&mut self) -> Vec<NotifyError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    fn add_event_at(&mut self, event: NotifyEvent, time: Instant) {
+        // events without paths (e.g. rescans) are debounced together
+        let path = event.paths.first().cloned().unwrap_or_default();
+
+        let path_events = self.events_by_path.entry(path).or_insert_with(|| PathEvents {
+            events: Vec::new(),
+            first_seen: time,
+            last_seen: time,
+        });
+
+        path_events.last_seen = time;
+        path_events.events.push(RawEvent::new(event, time));
+    }
+
+    fn add_error(&mut self, error: NotifyError) {
+        self.errors.push(error);
+    }
+}
+
+/// A leading-edge event processor.
+///
+/// The first event of a path is released right away and further events of the same path
+/// are dropped until the window has passed since that event.
+#[derive(Debug)]
+pub struct LeadingEdgeProcessor {
+    window: Duration,
+    last_released: HashMap<PathBuf, Instant>,
+    events: Vec<RawEvent>,
+    errors: Vec<NotifyError>,
+}
+
+impl LeadingEdgeProcessor {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            last_released: HashMap::new(),
+            events: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+}
+
+impl EventProcessor for LeadingEdgeProcessor {
+    fn get_events_at(&mut self, now: Instant) -> Vec<RawEvent> {
+        self.last_released
+            .retain(|_, released| now.saturating_duration_since(*released) < self.window);
+
+        std::mem::take(&mut self.events)
+    }
+
+    fn flush_events(&mut self) -> Vec<RawEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn get_errors(&mut self) -> Vec<NotifyError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    fn add_event_at(&mut self, event: NotifyEvent, time: Instant) {
+        let path = event.paths.first().cloned().unwrap_or_default();
+
+        if let Some(released) = self.last_released.get(&path) {
+            if time.saturating_duration_since(*released) < self.window {
+                return;
+            }
+        }
+
+        self.last_released.insert(path, time);
+        self.events.push(RawEvent::new(event, time));
+    }
+
+    fn add_error(&mut self, error: NotifyError) {
+        self.errors.push(error);
+    }
+}
+
+/// A throttling event processor.
+///
+/// All pending events are released together at most once per window,
+/// so every batch carries the latest known state.
+#[derive(Debug)]
+pub struct ThrottleProcessor {
+    window: Duration,
+    last_release: Option<Instant>,
+    events: Vec<RawEvent>,
+    errors: Vec<NotifyError>,
+}
+
+impl ThrottleProcessor {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            last_release: None,
+            events: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn release_events(&mut self, now: Instant) -> Vec<RawEvent> {
+        if self.events.is_empty() {
+            return Vec::new();
+        }
+
+        if self
+            .last_release
+            .is_some_and(|released| now.saturating_duration_since(released) < self.window)
+        {
+            return Vec::new();
+        }
+
+        self.last_release = Some(now);
+
+        std::mem::take(&mut self.events)
+    }
+}
+
+impl EventProcessor for ThrottleProcessor {
+    fn get_events_at(&mut self, now: Instant) -> Vec<RawEvent> {
+        self.release_events(now)
+    }
+
+    fn flush_events(&mut self) -> Vec<RawEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn get_errors(&mut self) -> Vec<NotifyError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    fn add_event_at(&mut self, event: NotifyEvent, time: Instant) {
+        self.events.push(RawEvent::new(event, time));
     }
 
     fn add_error(&mut self, error: NotifyError) {
         self.errors.push(error);
     }
 }
+
+/// How long the first half of a rename is held back, waiting for the second half
+pub(crate) const RENAME_PAIRING_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// Stitches the two halves of renames reported as separate events into one event with both paths
+/// before they're buffered by the wrapped processor, so delivery modes never release one half without the other.
+///
+/// The `From` half is held back until its `To` half arrives or `RENAME_PAIRING_TIMEOUT` passes,
+/// in which case the other side of the rename is not watched and the half is passed on as it is.
+/// The rename the backend reports once more with both paths afterwards is dropped.
+/// Single-path renames of unknown direction are resolved by checking whether the path exists when they arrive.
+#[derive(Debug)]
+pub struct RenamePairing<P> {
+    inner: P,
+    /// The `From` half waiting for its `To` half
+    pending_from: Option<RawEvent>,
+    /// The tracker of the last stitched rename
+    last_paired: Option<usize>,
+}
+
+impl<P: EventProcessor> RenamePairing<P> {
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            pending_from: None,
+            last_paired: None,
+        }
+    }
+
+    /// Pass the `From` half on as it is, since its `To` half is not coming
+    fn release_pending(&mut self) {
+        if let Some(from) = self.pending_from.take() {
+            self.inner.add_event_at(from.event, from.time);
+        }
+    }
+
+    fn handle_rename_to(&mut self, event: NotifyEvent, time: Instant) {
+        let from = self.pending_from.take_if(|from| {
+            match (from.tracker(), event.tracker()) {
+                (Some(from_tracker), Some(to_tracker)) => from_tracker == to_tracker,
+                // halves without trackers are only held back until the next event
+                (None, None) => true,
+                _ => false,
+            }
+        });
+
+        match from {
+            Some(mut from) => {
+                from.kind = EventKind::Modify(ModifyKind::Name(RenameMode::Both));
+                from.paths.push(event.paths[0].clone());
+
+                self.last_paired = from.tracker();
+                self.inner.add_event_at(from.event, from.time);
+            }
+            None => {
+                self.release_pending();
+                self.inner.add_event_at(event, time);
+            }
+        }
+    }
+}
+
+impl<P: EventProcessor> EventProcessor for RenamePairing<P> {
+    fn get_events_at(&mut self, now: Instant) -> Vec<RawEvent> {
+        if self
+            .pending_from
+            .as_ref()
+            .is_some_and(|from| now.saturating_duration_since(from.time) >= RENAME_PAIRING_TIMEOUT)
+        {
+            self.release_pending();
+        }
+
+        self.inner.get_events_at(now)
+    }
+
+    fn flush_events(&mut self) -> Vec<RawEvent> {
+        self.release_pending();
+
+        self.inner.flush_events()
+    }
+
+    fn get_errors(&mut self) -> Vec<NotifyError> {
+        self.inner.get_errors()
+    }
+
+    fn add_event_at(&mut self, mut event: NotifyEvent, time: Instant) {
+        if let EventKind::Modify(ModifyKind::Name(RenameMode::Any)) = event.kind {
+            if event.paths.len() == 1 {
+                let mode = if event.paths[0].exists() {
+                    RenameMode::To
+                } else {
+                    RenameMode::From
+                };
+                event.kind = EventKind::Modify(ModifyKind::Name(mode));
+            }
+        }
+
+        match event.kind {
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) if !event.paths.is_empty() => {
+                self.release_pending();
+                self.pending_from = Some(RawEvent::new(event, time));
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) if !event.paths.is_empty() => {
+                self.handle_rename_to(event, time);
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both))
+                if event.tracker().is_some() && event.tracker() == self.last_paired =>
+            {
+                // already stitched from its halves
+            }
+            _ => {
+                if self.pending_from.as_ref().is_some_and(|from| from.tracker().is_none()) {
+                    self.release_pending();
+                }
+
+                self.inner.add_event_at(event, time);
+            }
+        }
+    }
+
+    fn add_error(&mut self, error: NotifyError) {
+        self.inner.add_error(error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use notify::event::{CreateKind, DataChange};
+
+    fn modify(path: &str) -> NotifyEvent {
+        NotifyEvent::new(EventKind::Modify(ModifyKind::Data(DataChange::Content))).add_path(PathBuf::from(path))
+    }
+
+    fn create(path: &str) -> NotifyEvent {
+        NotifyEvent::new(EventKind::Create(CreateKind::File)).add_path(PathBuf::from(path))
+    }
+
+    #[test]
+    fn test_debounce_holds_events_until_quiet() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+
+        let mut p = DebounceProcessor::new(ms(100), None);
+
+        for i in 0..10 {
+            p.add_event_at(modify("/proj/a.txt"), start + ms(i * 50));
+        }
+
+        assert!(p.release_events(start + ms(500)).is_empty());
+        assert_eq!(p.release_events(start + ms(550)).len(), 10);
+    }
+
+    #[test]
+    fn test_debounce_paths_are_independent() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+
+        let mut p = DebounceProcessor::new(ms(100), None);
+
+        p.add_event_at(create("/proj/a.txt"), start);
+        p.add_event_at(modify("/proj/b.txt"), start);
+        p.add_event_at(modify("/proj/b.txt"), start + ms(80));
+
+        let released = p.release_events(start + ms(100));
+
+        assert_eq!(released.len(), 1);
+        assert_eq!(released[0].paths[0], PathBuf::from("/proj/a.txt"));
+        assert_eq!(p.release_events(start + ms(180)).len(), 2);
+    }
+
+    #[test]
+    fn test_debounce_max_wait() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+
+        let mut p = DebounceProcessor::new(ms(100), Some(ms(300)));
+
+        for i in 0..6 {
+            p.add_event_at(modify("/proj/a.txt"), start + ms(i * 50));
+        }
+
+        assert!(p.release_events(start + ms(250)).is_empty());
+        assert_eq!(p.release_events(start + ms(300)).len(), 6);
+    }
+
+    #[test]
+    fn test_debounce_flush() {
+        let mut p = DebounceProcessor::new(Duration::from_secs(10), None);
+
+        p.add_event(create("/proj/a.txt"));
+        p.add_event(modify("/proj/a.txt"));
+
+        assert!(p.get_events().is_empty());
+        assert_eq!(p.flush_events().len(), 2);
+    }
+
+    #[test]
+    fn test_leading_edge_releases_first_event_per_window() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+
+        let mut p = LeadingEdgeProcessor::new(ms(100));
+
+        p.add_event_at(modify("/proj/a.txt"), start);
+        p.add_event_at(modify("/proj/a.txt"), start + ms(50));
+        p.add_event_at(modify("/proj/b.txt"), start + ms(50));
+        p.add_event_at(modify("/proj/a.txt"), start + ms(120));
+
+        let released = p.flush_events();
+
+        assert_eq!(released.len(), 3);
+        assert_eq!(released[1].paths[0], PathBuf::from("/proj/b.txt"));
+        assert_eq!(released[2].time, start + ms(120));
+    }
+
+    #[test]
+    fn test_throttle_releases_at_most_once_per_window() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+
+        let mut p = ThrottleProcessor::new(ms(100));
+
+        p.add_event(modify("/proj/a.txt"));
+        assert_eq!(p.release_events(start).len(), 1);
+
+        p.add_event(modify("/proj/a.txt"));
+        p.add_event(modify("/proj/b.txt"));
+        assert!(p.release_events(start + ms(50)).is_empty());
+        assert_eq!(p.release_events(start + ms(100)).len(), 2);
+    }
+
+    fn rename(mode: RenameMode, paths: &[&str], tracker: Option<usize>) -> NotifyEvent {
+        let mut event = NotifyEvent::new(EventKind::Modify(ModifyKind::Name(mode)));
+
+        for path in paths {
+            event = event.add_path(PathBuf::from(path));
+        }
+
+        if let Some(tracker) = tracker {
+            event = event.set_tracker(tracker);
+        }
+
+        event
+    }
+
+    fn kinds(events: &[RawEvent]) -> Vec<EventKind> {
+        events.iter().map(|e| e.kind).collect()
+    }
+
+    /// Add the events one millisecond apart and return what's released once they're quiet
+    fn paired(mut p: impl EventProcessor, events: Vec<NotifyEvent>) -> Vec<RawEvent> {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+
+        for (i, event) in events.into_iter().enumerate() {
+            p.add_event_at(event, start + ms(i as u64));
+        }
+
+        p.get_events_at(start + ms(1000))
+    }
+
+    #[test]
+    fn test_rename_pairing_drops_stitched_halves() {
+        let events = paired(
+            RenamePairing::new(BatchProcessor::new(Duration::ZERO)),
+            vec![
+                rename(RenameMode::From, &["/w/a"], Some(1)),
+                rename(RenameMode::To, &["/w/b"], Some(1)),
+                rename(RenameMode::Both, &["/w/a", "/w/b"], Some(1)),
+            ],
+        );
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].paths, vec![PathBuf::from("/w/a"), PathBuf::from("/w/b")]);
+    }
+
+    #[test]
+    fn test_rename_pairing_joins_halves() {
+        let events = paired(
+            RenamePairing::new(BatchProcessor::new(Duration::ZERO)),
+            vec![
+                rename(RenameMode::From, &["/w/a"], Some(1)),
+                create("/w/c"),
+                rename(RenameMode::To, &["/w/b"], Some(1)),
+            ],
+        );
+
+        assert_eq!(
+            kinds(&events),
+            vec![
+                EventKind::Create(CreateKind::File),
+                EventKind::Modify(ModifyKind::Name(RenameMode::Both))
+            ]
+        );
+        assert_eq!(events[1].paths, vec![PathBuf::from("/w/a"), PathBuf::from("/w/b")]);
+    }
+
+    #[test]
+    fn test_rename_pairing_keeps_unmatched_halves() {
+        let events = paired(
+            RenamePairing::new(BatchProcessor::new(Duration::ZERO)),
+            vec![
+                rename(RenameMode::From, &["/w/a"], Some(1)),
+                rename(RenameMode::To, &["/w/b"], Some(2)),
+                rename(RenameMode::From, &["/w/c"], None),
+                create("/w/d"),
+                rename(RenameMode::To, &["/w/e"], None),
+            ],
+        );
+
+        assert_eq!(events.len(), 5);
+    }
+
+    #[test]
+    fn test_rename_pairing_per_path_debouncing() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+
+        // the halves have different paths, so they would be debounced separately
+        let mut p = RenamePairing::new(DebounceProcessor::new(ms(100), None));
+
+        p.add_event_at(rename(RenameMode::From, &["/w/a"], Some(1)), start);
+        p.add_event_at(modify("/w/x"), start + ms(10));
+        p.add_event_at(rename(RenameMode::To, &["/w/b"], Some(1)), start + ms(20));
+
+        let events = p.get_events_at(start + ms(200));
+
+        assert_eq!(
+            kinds(&events),
+            vec![
+                EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+                EventKind::Modify(ModifyKind::Data(DataChange::Content))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rename_pairing_leading_edge() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+
+        let mut p = RenamePairing::new(LeadingEdgeProcessor::new(ms(100)));
+
+        p.add_event_at(rename(RenameMode::From, &["/w/a"], Some(1)), start);
+
+        // a drain in between the halves doesn't release the first one on its own
+        assert!(p.get_events_at(start + ms(1)).is_empty());
+
+        p.add_event_at(rename(RenameMode::To, &["/w/b"], Some(1)), start + ms(2));
+
+        let events = p.get_events_at(start + ms(3));
+        assert_eq!(
+            kinds(&events),
+            vec![EventKind::Modify(ModifyKind::Name(RenameMode::Both))]
+        );
+    }
+
+    #[test]
+    fn test_rename_pairing_timeout() {
+        let start = Instant::now();
+
+        let mut p = RenamePairing::new(BatchProcessor::new(Duration::ZERO));
+
+        p.add_event_at(rename(RenameMode::From, &["/w/a"], Some(1)), start);
+
+        assert!(p.get_events_at(start + RENAME_PAIRING_TIMEOUT / 2).is_empty());
+
+        // moved out of the watched paths
+        let events = p.get_events_at(start + RENAME_PAIRING_TIMEOUT);
+        assert_eq!(
+            kinds(&events),
+            vec![EventKind::Modify(ModifyKind::Name(RenameMode::From))]
+        );
+    }
+}
@@ -0,0 +1,97 @@
+mod snapshot;
+mod watcher;
+
+use std::io::ErrorKind as IOErrorKind;
+
+use pyo3::exceptions::{PyException, PyFileNotFoundError, PyOSError, PyPermissionError};
+use pyo3::prelude::*;
+use tokio::runtime::Builder;
+
+use crate::error::WatchError;
+use crate::events::access::{AccessEvent, AccessMode, AccessType};
+use crate::events::base::ObjectType;
+use crate::events::create::CreateEvent;
+use crate::events::delete::DeleteEvent;
+use crate::events::directory::DirectoryChangedEvent;
+use crate::events::modify::{
+    DataType, MetadataType, ModifyDataEvent, ModifyMetadataEvent, ModifyOtherEvent, ModifyUnknownEvent,
+};
+use crate::events::moved::{MovedInEvent, MovedOutEvent};
+use crate::events::rename::RenameEvent;
+use crate::events::rescan::RescanEvent;
+use crate::events::serialize::{event_from_dict, event_from_json};
+use crate::python::snapshot::SnapshotWrapper;
+use crate::python::watcher::{EventBatchIter, WatcherWrapper};
+
+pyo3::create_exception!(_inotify_toolkit_lib, WatcherError, PyException);
+pyo3::create_exception!(_inotify_toolkit_lib, EventsLost, WatcherError);
+
+impl From<WatchError> for PyErr {
+    fn from(err: WatchError) -> Self {
+        let message = err.to_string();
+
+        match err {
+            WatchError::Closed
+            | WatchError::SubscriptionExists(_)
+            | WatchError::NoSuchSubscription(_)
+            | WatchError::DefaultSubscription => WatcherError::new_err(message),
+            WatchError::Lagged(_) => EventsLost::new_err(message),
+            WatchError::PathNotFound(_) => PyFileNotFoundError::new_err(message),
+            WatchError::PermissionDenied(_) => PyPermissionError::new_err(message),
+            WatchError::Snapshot(e) if e.kind() == IOErrorKind::NotFound => PyFileNotFoundError::new_err(e.to_string()),
+            WatchError::Notify(e) => PyOSError::new_err(format!("{message} ({e:?})")),
+            _ => PyOSError::new_err(message),
+        }
+    }
+}
+
+#[pymodule]
+fn _notifykit_lib(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+
+    let mut builder = Builder::new_multi_thread();
+    builder.enable_all();
+    pyo3_async_runtimes::tokio::init(builder);
+
+    let mut version = env!("CARGO_PKG_VERSION").to_string();
+    version = version.replace("-alpha", "a").replace("-beta", "b");
+
+    m.add("__version__", version)?;
+
+    m.add("WatcherError", py.get_type::<WatcherError>())?;
+    m.add("EventsLost", py.get_type::<EventsLost>())?;
+
+    m.add_class::<WatcherWrapper>()?;
+    m.add_class::<SnapshotWrapper>()?;
+    m.add_class::<EventBatchIter>()?;
+
+    // Create & Delete Events
+    m.add_class::<ObjectType>()?;
+    m.add_class::<CreateEvent>()?;
+    m.add_class::<DeleteEvent>()?;
+
+    // Access Event
+    m.add_class::<AccessType>()?;
+    m.add_class::<AccessMode>()?;
+    m.add_class::<AccessEvent>()?;
+
+    // Modify Event
+    m.add_class::<MetadataType>()?;
+    m.add_class::<DataType>()?;
+
+    m.add_class::<ModifyMetadataEvent>()?;
+    m.add_class::<ModifyDataEvent>()?;
+    m.add_class::<ModifyOtherEvent>()?;
+    m.add_class::<ModifyUnknownEvent>()?;
+
+    m.add_class::<RenameEvent>()?;
+    m.add_class::<MovedInEvent>()?;
+    m.add_class::<MovedOutEvent>()?;
+    m.add_class::<RescanEvent>()?;
+    m.add_class::<DirectoryChangedEvent>()?;
+
+    m.add_function(wrap_pyfunction!(event_from_dict, m)?)?;
+    m.add_function(wrap_pyfunction!(event_from_json, m)?)?;
+
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+
+use notify::RecursiveMode;
+use pyo3::prelude::*;
+use pyo3::types::PyList;
+
+use crate::error::WatchError;
+use crate::file_cache::FileCache;
+use crate::python::watcher::build_filter;
+
+/// Paths under a root along with their file IDs, sizes and modification times at some point in time.
+#[pyclass]
+pub(crate) struct SnapshotWrapper {
+    cache: FileCache,
+}
+
+#[pymethods]
+impl SnapshotWrapper {
+    #[staticmethod]
+    #[pyo3(signature = (root, recursive=true, ignore_dirs=vec![], ignore_patterns=vec![], ignore_paths=vec![]))]
+    fn take(
+        py: Python<'_>,
+        root: PathBuf,
+        recursive: bool,
+        ignore_dirs: Vec<String>,
+        ignore_patterns: Vec<String>,
+        ignore_paths: Vec<String>,
+    ) -> PyResult<Self> {
+        if !root.exists() {
+            return Err(WatchError::PathNotFound(format!("No such file or directory: {}", root.display())).into());
+        }
+
+        let mode = if recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+
+        let mut cache = match build_filter(ignore_dirs, ignore_patterns, ignore_paths)? {
+            Some(filter) => FileCache::with_filter(filter),
+            None => FileCache::new(),
+        };
+
+        py.detach(|| cache.add_root(root, mode));
+
+        Ok(Self { cache })
+    }
+
+    #[staticmethod]
+    fn load(path: PathBuf) -> PyResult<Self> {
+        let cache = FileCache::load(&path).map_err(WatchError::Snapshot)?;
+
+        Ok(Self { cache })
+    }
+
+    fn save(&self, path: PathBuf) -> PyResult<()> {
+        Ok(self.cache.save(path).map_err(WatchError::Io)?)
+    }
+
+    /// Returns events that turn this snapshot into the other one.
+    fn diff<'py>(&self, py: Python<'py>, other: &SnapshotWrapper) -> PyResult<Bound<'py, PyList>> {
+        let events = other.cache.diff_since(&self.cache);
+
+        PyList::new(py, &events)
+    }
+
+    fn __len__(&self) -> usize {
+        self.cache.len()
+    }
+}
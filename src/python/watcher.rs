@@ -0,0 +1,340 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use pyo3::exceptions::{PyOSError, PyStopAsyncIteration, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyList;
+
+use crate::channel::{BackpressurePolicy, BatchReceiver, RecvError};
+use crate::error::WatchError;
+use crate::filter::EventFilter;
+use crate::processor::{DeliveryMode, DeliveryOptions};
+use crate::python::EventsLost;
+use crate::subscription::Pipeline;
+use crate::watcher::{Watcher, WatcherOptions};
+
+#[pyclass]
+pub struct WatcherWrapper {
+    inner: Arc<Mutex<Watcher>>,
+}
+
+#[pymethods]
+impl WatcherWrapper {
+    #[new]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (debounce_ms, event_buffer_size, debug, follow_symlinks, backpressure="drop_oldest", mode="delay", max_wait_ms=None, expand_dir_renames=false, expand_dir_deletes=false, ignore_noise=false))]
+    fn __init__(
+        debounce_ms: u64,
+        event_buffer_size: usize,
+        debug: bool,
+        follow_symlinks: bool,
+        backpressure: &str,
+        mode: &str,
+        max_wait_ms: Option<u64>,
+        expand_dir_renames: bool,
+        expand_dir_deletes: bool,
+        ignore_noise: bool,
+    ) -> PyResult<Self> {
+        let inner = Watcher::new(WatcherOptions {
+            delivery: parse_delivery(mode, debounce_ms, max_wait_ms)?,
+            event_buffer_size,
+            debug,
+            follow_symlinks,
+            backpressure: parse_backpressure(backpressure)?,
+            expand_dir_renames,
+            expand_dir_deletes,
+            ignore_noise,
+        })?;
+
+        Ok(WatcherWrapper {
+            inner: Arc::new(Mutex::new(inner)),
+        })
+    }
+
+    pub fn watch<'py>(
+        &self,
+        py: Python<'py>,
+        paths: Vec<PathBuf>,
+        recursive: bool,
+        ignore_permission_errors: bool,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let watcher = Arc::clone(&self.inner);
+
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let res = tokio::task::spawn_blocking(move || {
+                let mut guard = watcher.lock().map_err(WatchError::from)?;
+
+                Ok(guard.watch(&paths, recursive, ignore_permission_errors)?)
+            })
+            .await;
+
+            match res {
+                Ok(inner) => inner,
+                Err(join_err) => Err(PyOSError::new_err(join_err.to_string())),
+            }
+        })
+    }
+
+    pub fn unwatch<'py>(&mut self, py: Python<'py>, paths: Vec<PathBuf>) -> PyResult<Bound<'py, PyAny>> {
+        let watcher = self.inner.clone();
+
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let res = tokio::task::spawn_blocking(move || {
+                let mut guard = watcher.lock().map_err(WatchError::from)?;
+
+                Ok(guard.unwatch(&paths)?)
+            })
+            .await;
+
+            match res {
+                Ok(inner) => inner,
+                Err(join_err) => Err(PyOSError::new_err(join_err.to_string())),
+            }
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (tick_ms, ignore_dirs=vec![], ignore_patterns=vec![], ignore_paths=vec![], collapse=false, max_batch_size=None, storm_threshold=None, aggregate_threshold=None))]
+    fn events(
+        &self,
+        tick_ms: u64,
+        ignore_dirs: Vec<String>,
+        ignore_patterns: Vec<String>,
+        ignore_paths: Vec<String>,
+        collapse: bool,
+        max_batch_size: Option<usize>,
+        storm_threshold: Option<usize>,
+        aggregate_threshold: Option<usize>,
+    ) -> PyResult<EventBatchIter> {
+        let tick = parse_tick(tick_ms)?;
+        let pipeline = Pipeline {
+            event_filter: build_filter(ignore_dirs, ignore_patterns, ignore_paths)?,
+            collapse,
+            max_batch_size: positive("max_batch_size", max_batch_size)?,
+            storm_threshold: positive("storm_threshold", storm_threshold)?,
+            aggregate_threshold: positive("aggregate_threshold", aggregate_threshold)?,
+            ..Pipeline::default()
+        };
+
+        let rx = {
+            let mut g = self.watcher()?;
+
+            if g.is_closed() {
+                return Err(WatchError::Closed.into());
+            }
+
+            let rx = g.subscribe();
+            g.start_drain(tick, pipeline);
+
+            rx
+        };
+
+        Ok(EventBatchIter::new(rx))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (name, debounce_ms, tick_ms, event_buffer_size, backpressure, mode, max_wait_ms=None, ignore_dirs=vec![], ignore_patterns=vec![], ignore_paths=vec![], collapse=false, max_batch_size=None, storm_threshold=None, aggregate_threshold=None))]
+    fn subscribe(
+        &self,
+        name: String,
+        debounce_ms: u64,
+        tick_ms: u64,
+        event_buffer_size: usize,
+        backpressure: &str,
+        mode: &str,
+        max_wait_ms: Option<u64>,
+        ignore_dirs: Vec<String>,
+        ignore_patterns: Vec<String>,
+        ignore_paths: Vec<String>,
+        collapse: bool,
+        max_batch_size: Option<usize>,
+        storm_threshold: Option<usize>,
+        aggregate_threshold: Option<usize>,
+    ) -> PyResult<EventBatchIter> {
+        let tick = parse_tick(tick_ms)?;
+        let pipeline = Pipeline {
+            event_filter: build_filter(ignore_dirs, ignore_patterns, ignore_paths)?,
+            collapse,
+            max_batch_size: positive("max_batch_size", max_batch_size)?,
+            storm_threshold: positive("storm_threshold", storm_threshold)?,
+            aggregate_threshold: positive("aggregate_threshold", aggregate_threshold)?,
+            ..Pipeline::default()
+        };
+        let delivery = parse_delivery(mode, debounce_ms, max_wait_ms)?;
+        let backpressure = parse_backpressure(backpressure)?;
+
+        let mut g = self.watcher()?;
+
+        if g.is_closed() {
+            return Err(WatchError::Closed.into());
+        }
+
+        let rx = g.add_subscription(name, delivery, event_buffer_size, tick, pipeline, backpressure)?;
+
+        Ok(EventBatchIter::new(rx))
+    }
+
+    pub fn unsubscribe(&self, name: String) -> PyResult<()> {
+        let mut g = self.watcher()?;
+
+        Ok(g.remove_subscription(&name)?)
+    }
+
+    pub fn suppress(&self, paths: Vec<PathBuf>) -> PyResult<u64> {
+        let g = self.watcher()?;
+
+        Ok(g.suppress(paths)?)
+    }
+
+    pub fn release(&self, suppress_id: u64) -> PyResult<()> {
+        let g = self.watcher()?;
+
+        Ok(g.release(suppress_id)?)
+    }
+
+    pub fn expect_write(&self, paths: Vec<PathBuf>, timeout_ms: u64) -> PyResult<()> {
+        let g = self.watcher()?;
+
+        Ok(g.expect_write(paths, Duration::from_millis(timeout_ms))?)
+    }
+
+    pub fn save_snapshot(&self, py: Python<'_>, path: PathBuf) -> PyResult<()> {
+        let g = self.watcher()?;
+
+        Ok(py.detach(|| g.save_snapshot(&path))?)
+    }
+
+    pub fn changes_since<'py>(&self, py: Python<'py>, path: PathBuf) -> PyResult<Bound<'py, PyList>> {
+        let g = self.watcher()?;
+
+        let events = py.detach(|| g.changes_since(&path))?;
+
+        PyList::new(py, &events)
+    }
+
+    pub fn record(&self, path: PathBuf) -> PyResult<()> {
+        let mut g = self.watcher()?;
+
+        Ok(g.record(&path)?)
+    }
+
+    #[pyo3(signature = (flush=false))]
+    pub fn stop(&self, flush: bool) {
+        if let Ok(mut g) = self.inner.lock() {
+            g.stop(flush);
+        }
+    }
+
+    pub fn close(&self) -> PyResult<()> {
+        let mut g = self.watcher()?;
+
+        g.close();
+
+        Ok(())
+    }
+
+    pub fn __repr__(&mut self) -> PyResult<String> {
+        let mut watcher = self.watcher()?;
+
+        Ok(watcher.repr())
+    }
+}
+
+impl WatcherWrapper {
+    fn watcher(&self) -> Result<MutexGuard<'_, Watcher>, WatchError> {
+        Ok(self.inner.lock()?)
+    }
+}
+
+pub(crate) fn build_filter(
+    ignore_dirs: Vec<String>,
+    ignore_patterns: Vec<String>,
+    ignore_paths: Vec<String>,
+) -> Result<Option<EventFilter>, WatchError> {
+    if ignore_dirs.is_empty() && ignore_patterns.is_empty() && ignore_paths.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(EventFilter::new(ignore_dirs, ignore_patterns, ignore_paths)?))
+}
+
+fn parse_delivery(mode: &str, debounce_ms: u64, max_wait_ms: Option<u64>) -> PyResult<DeliveryOptions> {
+    Ok(DeliveryOptions {
+        mode: mode.parse::<DeliveryMode>().map_err(PyValueError::new_err)?,
+        buffering_time: Duration::from_millis(debounce_ms),
+        max_wait: max_wait_ms.map(Duration::from_millis),
+    })
+}
+
+fn positive<T: Default + PartialEq>(name: &str, value: Option<T>) -> PyResult<Option<T>> {
+    match value {
+        Some(ref value) if *value == T::default() => {
+            Err(PyValueError::new_err(format!("{name} must be greater than zero")))
+        }
+        _ => Ok(value),
+    }
+}
+
+/// A zero interval can't drive the drain loop.
+fn parse_tick(tick_ms: u64) -> PyResult<Duration> {
+    positive("tick_ms", Some(tick_ms))?;
+
+    Ok(Duration::from_millis(tick_ms))
+}
+
+fn parse_backpressure(backpressure: &str) -> PyResult<BackpressurePolicy> {
+    backpressure.parse().map_err(PyValueError::new_err)
+}
+
+#[pyclass]
+pub(crate) struct EventBatchIter {
+    rx: Arc<tokio::sync::Mutex<BatchReceiver>>,
+}
+
+impl EventBatchIter {
+    fn new(rx: BatchReceiver) -> Self {
+        Self {
+            rx: Arc::new(tokio::sync::Mutex::new(rx)),
+        }
+    }
+}
+
+#[pymethods]
+impl EventBatchIter {
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __anext__<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyAny>>> {
+        let rx = Arc::clone(&self.rx);
+
+        let fut = pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let mut guard = rx.lock().await;
+            let batch = loop {
+                match guard.recv().await {
+                    // only the raise policy makes lost batches the consumer's problem
+                    Err(RecvError::Lagged(n)) if guard.backpressure() != BackpressurePolicy::Raise => {
+                        eprintln!("notifykit: consumer too slow, {n} event batch(es) dropped");
+                    }
+                    batch => break batch,
+                }
+            };
+
+            match batch {
+                Ok(batch) => Python::attach(|py| {
+                    let list = PyList::new(py, &batch)?;
+                    Ok(list.into_any().unbind())
+                }),
+                Err(RecvError::Lagged(n)) => Python::attach(|py| {
+                    let err = EventsLost::new_err(format!("consumer too slow, {n} event batch(es) dropped"));
+                    err.value(py).setattr("count", n)?;
+                    Err(err)
+                }),
+                Err(RecvError::Closed) => Err(PyErr::new::<PyStopAsyncIteration, _>("event stream closed")),
+            }
+        })?;
+
+        Ok(Some(fut))
+    }
+}
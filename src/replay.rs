@@ -0,0 +1,493 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::PoisonError;
+use std::time::{Duration, Instant};
+
+use notify::Event;
+use serde::{Deserialize, Serialize};
+
+use crate::error::WatchError;
+use crate::events::EventType;
+use crate::file_cache::{CacheOptions, FileCache};
+use crate::processor::{DeliveryOptions, EventProcessor, RawEvent};
+use crate::subscription::Pipeline;
+
+/// An event of the backend along with the time it has arrived at, relative to the start of the recording.
+///
+/// Recordings are files with an event per line, where the time is in microseconds
+/// (e.g. `{"elapsed_us": 1500, "event": {"type": "create", "kind": "file", "paths": ["/a.txt"], "attrs": {}}}`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    #[serde(rename = "elapsed_us", with = "micros")]
+    pub elapsed: Duration,
+    pub event: Event,
+}
+
+impl RecordedEvent {
+    pub fn new(elapsed: Duration, event: Event) -> Self {
+        Self { elapsed, event }
+    }
+}
+
+mod micros {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_micros() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_micros)
+    }
+}
+
+/// Writes backend events to a recording (see `Watcher::record()`).
+pub(crate) struct Recorder {
+    started: Instant,
+    out: BufWriter<File>,
+}
+
+impl Recorder {
+    pub(crate) fn create(path: impl AsRef<Path>) -> Result<Self, WatchError> {
+        Ok(Self {
+            started: Instant::now(),
+            out: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    pub(crate) fn record(&mut self, event: &Event) -> io::Result<()> {
+        let recorded = RecordedEvent::new(self.started.elapsed(), event.clone());
+
+        serde_json::to_writer(&mut self.out, &recorded)?;
+        self.out.write_all(b"\n")?;
+
+        // the recording is meant for bug reports, so it should be complete even if the process is killed
+        self.out.flush()
+    }
+}
+
+/// Read the events of a recording made by `Watcher::record()`.
+pub fn read_recording(path: impl AsRef<Path>) -> Result<Vec<RecordedEvent>, WatchError> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut events = Vec::new();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let event = serde_json::from_str(&line).map_err(|e| WatchError::InvalidRecording(index + 1, e))?;
+        events.push(event);
+    }
+
+    Ok(events)
+}
+
+/// A batch delivered during a replay, with the time of the drain it was delivered at
+#[derive(Debug, Clone)]
+pub struct ReplayedBatch {
+    pub elapsed: Duration,
+    pub events: Vec<EventType>,
+}
+
+/// A backend feeding recorded events through an event processor and pipeline.
+///
+/// Events are added and drained on the timeline of the recording rather than the clock,
+/// so the batches only depend on the recording and the settings, e.g. to reproduce debouncing issues in tests.
+/// The only exception are single-path renames of unknown direction (reported on some platforms),
+/// which are resolved by checking whether the path exists.
+///
+/// Sessions of watchers with a file cache (see `WatcherOptions`) are replayed with a copy of `file_cache`,
+/// e.g. restored from a snapshot of the watched paths taken when the recording started.
+/// Events flagged for a rescan are replayed as they are, but the disk isn't walked, as it doesn't match the recording.
+#[derive(Debug, Default)]
+pub struct Replay {
+    /// How often the processor is drained, the same as the drain interval of the watcher
+    pub tick: Duration,
+    pub pipeline: Pipeline,
+    /// The state of the watched paths at the start of the recording
+    pub file_cache: Option<FileCache>,
+    pub cache_options: CacheOptions,
+}
+
+impl Replay {
+    pub fn new(tick: Duration, pipeline: Pipeline) -> Self {
+        Self {
+            tick,
+            pipeline,
+            ..Self::default()
+        }
+    }
+
+    /// Replay the events through a copy of the file cache, the way a watcher with a file cache would.
+    pub fn with_file_cache(mut self, file_cache: FileCache, options: CacheOptions) -> Self {
+        self.file_cache = Some(file_cache);
+        self.cache_options = options;
+
+        self
+    }
+
+    /// Replay the events (in the order they were recorded) through the processor of the delivery options, the way the watcher would deliver them.
+    pub fn run(&self, delivery: &DeliveryOptions, events: &[RecordedEvent]) -> Vec<ReplayedBatch> {
+        let processor = delivery.processor();
+        let mut processor = processor.lock().unwrap_or_else(PoisonError::into_inner);
+
+        self.replay(
+            &mut *processor,
+            events,
+            delivery.max_buffering_time(),
+            delivery.is_immediate(),
+        )
+    }
+
+    /// Replay the events through any processor (e.g. `CrossPlatformEventProcessor`),
+    /// draining it for `settle_time` after the last event before the rest of its events are flushed.
+    pub fn run_with(
+        &self,
+        processor: &mut dyn EventProcessor,
+        events: &[RecordedEvent],
+        settle_time: Duration,
+    ) -> Vec<ReplayedBatch> {
+        self.replay(processor, events, settle_time, false)
+    }
+
+    fn replay(
+        &self,
+        processor: &mut dyn EventProcessor,
+        events: &[RecordedEvent],
+        settle_time: Duration,
+        immediate: bool,
+    ) -> Vec<ReplayedBatch> {
+        // a zero interval would never move the clock forward
+        let tick = self.tick.max(Duration::from_millis(1));
+        let started = Instant::now();
+        let mut batches = Vec::new();
+        let mut file_cache = self.file_cache.clone();
+        // events released as soon as they arrive can't be held back
+        let hold = !immediate;
+
+        // the drain loop ticks right away when it starts, along with the watcher
+        let mut next_tick = Duration::ZERO;
+
+        for recorded in events {
+            // events arriving at the same time as the tick come after it
+            while next_tick <= recorded.elapsed {
+                self.drain(processor, file_cache.as_ref(), started, next_tick, hold, &mut batches);
+                next_tick += tick;
+            }
+
+            let events = match file_cache {
+                Some(ref mut cache) => cache.intake(recorded.event.clone(), self.cache_options),
+                None => vec![recorded.event.clone()],
+            };
+
+            for event in events {
+                processor.add_event_at(event, started + recorded.elapsed);
+            }
+
+            // the drain loop is woken up by new events in the immediate modes
+            if immediate {
+                self.drain(
+                    processor,
+                    file_cache.as_ref(),
+                    started,
+                    recorded.elapsed,
+                    hold,
+                    &mut batches,
+                );
+            }
+        }
+
+        let settled = events.last().map(|recorded| recorded.elapsed).unwrap_or_default() + settle_time;
+
+        // up to the first tick once everything is settled
+        loop {
+            self.drain(processor, file_cache.as_ref(), started, next_tick, hold, &mut batches);
+
+            if next_tick >= settled && !self.pipeline.is_holding() {
+                break;
+            }
+
+            next_tick += tick;
+        }
+
+        // events still held back are delivered as the final batch, the same as when the watcher stops with a flush
+        self.deliver(
+            self.pipeline.flush_events(processor),
+            file_cache.as_ref(),
+            next_tick,
+            &mut batches,
+        );
+
+        batches
+    }
+
+    fn drain(
+        &self,
+        processor: &mut dyn EventProcessor,
+        file_cache: Option<&FileCache>,
+        started: Instant,
+        elapsed: Duration,
+        hold: bool,
+        batches: &mut Vec<ReplayedBatch>,
+    ) {
+        let events = self.pipeline.ready_events(processor, started + elapsed, hold);
+
+        self.deliver(events, file_cache, elapsed, batches);
+    }
+
+    fn deliver(
+        &self,
+        events: Vec<RawEvent>,
+        file_cache: Option<&FileCache>,
+        elapsed: Duration,
+        batches: &mut Vec<ReplayedBatch>,
+    ) {
+        if events.is_empty() {
+            return;
+        }
+
+        let batch = self.pipeline.process_with(events, file_cache);
+
+        batches.extend(
+            self.pipeline
+                .split(batch)
+                .into_iter()
+                .map(|events| ReplayedBatch { elapsed, events }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use notify::RecursiveMode;
+    use notify::event::{CreateKind, DataChange, EventKind, MetadataKind, ModifyKind, RemoveKind, RenameMode};
+
+    use crate::events::base::ObjectType;
+    use crate::file_cache::NoCache;
+    use crate::processor::{CrossPlatformEventProcessor, DeliveryMode};
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    fn at(elapsed: u64, kind: EventKind, path: &str) -> RecordedEvent {
+        RecordedEvent::new(ms(elapsed), Event::new(kind).add_path(PathBuf::from(path)))
+    }
+
+    fn modify(elapsed: u64, path: &str) -> RecordedEvent {
+        at(elapsed, EventKind::Modify(ModifyKind::Data(DataChange::Content)), path)
+    }
+
+    fn rename(elapsed: u64, mode: RenameMode, path: &str) -> RecordedEvent {
+        let mut recorded = at(elapsed, EventKind::Modify(ModifyKind::Name(mode)), path);
+        recorded.event = recorded.event.set_tracker(1);
+        recorded
+    }
+
+    fn timeline(batches: &[ReplayedBatch]) -> Vec<(Duration, Vec<&'static str>)> {
+        batches
+            .iter()
+            .map(|batch| (batch.elapsed, batch.events.iter().map(EventType::kind).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_recording_round_trip() {
+        let path = std::env::temp_dir().join(format!("notifykit-recording-{}", std::process::id()));
+        let event = Event::new(EventKind::Create(CreateKind::File))
+            .add_path(PathBuf::from("/w/a"))
+            .set_tracker(1);
+
+        let mut recorder = Recorder::create(&path).unwrap();
+        recorder.record(&event).unwrap();
+        recorder.record(&event).unwrap();
+        drop(recorder);
+
+        let recorded = read_recording(&path).unwrap();
+
+        assert_eq!(recorded.len(), 2);
+        assert_eq!(recorded[0].event, event);
+        assert!(recorded[0].elapsed <= recorded[1].elapsed);
+
+        std::fs::write(&path, "{\"elapsed_us\": 10, \"event\": {}}\n").unwrap();
+        assert!(matches!(read_recording(&path), Err(WatchError::InvalidRecording(1, _))));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_replay_debounce() {
+        let delivery = DeliveryOptions {
+            mode: DeliveryMode::Debounce,
+            buffering_time: ms(100),
+            max_wait: None,
+        };
+        let mut events: Vec<RecordedEvent> = (0..5).map(|i| modify(i * 50, "/w/a")).collect();
+        events.insert(3, modify(120, "/w/b"));
+
+        let batches = Replay::new(ms(50), Pipeline::default()).run(&delivery, &events);
+
+        // b has been quiet since 120ms, while a keeps changing until 200ms
+        assert_eq!(
+            timeline(&batches),
+            vec![(ms(250), vec!["modify_data"]), (ms(300), vec!["modify_data"; 5])]
+        );
+    }
+
+    #[test]
+    fn test_replay_is_deterministic() {
+        let delivery = DeliveryOptions {
+            buffering_time: ms(100),
+            ..DeliveryOptions::default()
+        };
+        let events: Vec<RecordedEvent> = (0..20).map(|i| modify(i * 30, &format!("/w/{}", i % 3))).collect();
+        let replay = Replay::new(ms(50), Pipeline::default());
+
+        let first = timeline(&replay.run(&delivery, &events));
+
+        assert_eq!(first.len(), 13);
+        assert_eq!(first, timeline(&replay.run(&delivery, &events)));
+    }
+
+    #[test]
+    fn test_replay_max_batch_size() {
+        let pipeline = Pipeline {
+            max_batch_size: Some(2),
+            ..Pipeline::default()
+        };
+
+        let events: Vec<RecordedEvent> = (0..5).map(|i| modify(0, &format!("/w/{i}"))).collect();
+        let batches = Replay::new(ms(50), pipeline).run(&DeliveryOptions::default(), &events);
+
+        assert_eq!(
+            batches.iter().map(|batch| batch.events.len()).collect::<Vec<_>>(),
+            vec![2, 2, 1]
+        );
+        assert!(batches.iter().all(|batch| batch.elapsed == ms(200)));
+    }
+
+    #[test]
+    fn test_replay_storm_across_ticks() {
+        let pipeline = Pipeline {
+            storm_threshold: Some(3),
+            ..Pipeline::default()
+        };
+        let delivery = DeliveryOptions {
+            buffering_time: ms(100),
+            ..DeliveryOptions::default()
+        };
+
+        // half of the burst is ready by the tick at 150ms and held back until the other half is ready by the next one
+        let events: Vec<RecordedEvent> = [40, 45, 55, 60]
+            .iter()
+            .map(|&t| modify(t, &format!("/w/{t}")))
+            .collect();
+        let batches = Replay::new(ms(50), pipeline).run(&delivery, &events);
+
+        assert_eq!(timeline(&batches), vec![(ms(200), vec!["rescan"])]);
+    }
+
+    #[test]
+    fn test_replay_pairs_renames_with_storm_threshold() {
+        let delivery = DeliveryOptions {
+            buffering_time: ms(100),
+            ..DeliveryOptions::default()
+        };
+        let events = vec![
+            modify(0, "/w/a"),
+            rename(99, RenameMode::From, "/w/a"),
+            rename(101, RenameMode::To, "/w/b"),
+        ];
+
+        for storm_threshold in [None, Some(1000)] {
+            let pipeline = Pipeline {
+                storm_threshold,
+                ..Pipeline::default()
+            };
+            let batches = Replay::new(ms(50), pipeline).run(&delivery, &events);
+            let kinds: Vec<&str> = timeline(&batches).into_iter().flat_map(|(_, kinds)| kinds).collect();
+
+            // the From half waits for its pair on the clock of the drain rather than of the next one
+            assert_eq!(
+                kinds,
+                vec!["modify_data", "rename"],
+                "storm threshold: {storm_threshold:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_replay_with_file_cache() {
+        let root = std::env::temp_dir().join(format!("notifykit-replay-cache-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("dir")).unwrap();
+        std::fs::write(root.join("dir/a.txt"), "a").unwrap();
+
+        let mut file_cache = FileCache::new();
+        file_cache.add_root(&root, RecursiveMode::Recursive);
+
+        let options = CacheOptions {
+            expand_dir_deletes: true,
+            ignore_noise: true,
+        };
+        let events = vec![
+            at(
+                10,
+                EventKind::Modify(ModifyKind::Metadata(MetadataKind::AccessTime)),
+                &root.join("dir/a.txt").to_string_lossy(),
+            ),
+            at(
+                20,
+                EventKind::Remove(RemoveKind::Any),
+                &root.join("dir").to_string_lossy(),
+            ),
+        ];
+        let replay = Replay::new(ms(50), Pipeline::default()).with_file_cache(file_cache, options);
+        let batches = replay.run(&DeliveryOptions::default(), &events);
+
+        // the access is noise, while the children of the directory are removed along with it
+        assert_eq!(timeline(&batches), vec![(ms(250), vec!["delete", "delete"])]);
+        assert!(matches!(
+            &batches[0].events[1],
+            EventType::Delete(e) if e.path == root.join("dir") && e.file_type == ObjectType::Dir
+        ));
+        // every run starts with the same cache
+        assert_eq!(
+            timeline(&replay.run(&DeliveryOptions::default(), &events)),
+            timeline(&batches)
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_cross_platform_created_and_renamed() {
+        let events = vec![
+            at(0, EventKind::Create(CreateKind::File), "/w/a"),
+            modify(10, "/w/a"),
+            rename(20, RenameMode::From, "/w/a"),
+            rename(20, RenameMode::To, "/w/b"),
+        ];
+
+        let mut processor = CrossPlatformEventProcessor::new(NoCache, ms(100));
+        let batches = Replay::new(ms(50), Pipeline::default()).run_with(&mut processor, &events, ms(100));
+
+        // the file didn't exist before, so it's only reported as created at its new path
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].elapsed, ms(100));
+        assert!(matches!(
+            &batches[0].events[..],
+            [EventType::Create(e)] if e.path == Path::new("/w/b") && e.file_type == ObjectType::File
+        ));
+    }
+}
@@ -0,0 +1,27 @@
+use tokio::runtime::Runtime;
+
+/// The runtime drain tasks of subscriptions are spawned on.
+///
+/// With the Python bindings, it's the runtime of `pyo3-async-runtimes` that the awaitables of the bindings run on.
+#[cfg(feature = "python")]
+pub(crate) fn runtime() -> &'static Runtime {
+    pyo3_async_runtimes::tokio::get_runtime()
+}
+
+/// The runtime drain tasks of subscriptions are spawned on.
+///
+/// It's started on first use, so the watcher works whether or not the caller runs a runtime of its own.
+#[cfg(not(feature = "python"))]
+pub(crate) fn runtime() -> &'static Runtime {
+    use std::sync::OnceLock;
+
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .thread_name("notifykit")
+            .enable_all()
+            .build()
+            .expect("failed to start the notifykit runtime")
+    })
+}
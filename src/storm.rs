@@ -0,0 +1,88 @@
+use std::path::{Path, PathBuf};
+
+use crate::events::EventType;
+use crate::events::rescan::RescanEvent;
+
+/// Replace a batch that is too large to be reported event by event
+/// with a single rescan notification for the subtree all its events happened in.
+pub(crate) fn summarize(events: &[EventType]) -> EventType {
+    let mut root: Option<PathBuf> = None;
+
+    for path in events.iter().flat_map(event_paths) {
+        root = Some(match root {
+            None => path.to_path_buf(),
+            Some(root) => common_ancestor(&root, path),
+        });
+    }
+
+    EventType::Rescan(RescanEvent::new(root.unwrap_or_default(), events.len()))
+}
+
+fn event_paths(event: &EventType) -> Vec<&Path> {
+    match event {
+        EventType::Rename(e) => vec![e.old_path.as_path(), e.new_path.as_path()],
+        _ => event.path().into_iter().collect(),
+    }
+}
+
+fn common_ancestor(left: &Path, right: &Path) -> PathBuf {
+    left.components()
+        .zip(right.components())
+        .take_while(|(l, r)| l == r)
+        .map(|(l, _)| l)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::events::base::ObjectType;
+    use crate::events::create::CreateEvent;
+    use crate::events::rename::RenameEvent;
+
+    fn create(path: &str) -> EventType {
+        EventType::Create(CreateEvent::new(PathBuf::from(path), ObjectType::File))
+    }
+
+    fn rescan(event: EventType) -> RescanEvent {
+        match event {
+            EventType::Rescan(e) => e,
+            other => panic!("expected a rescan event, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_summarize_subtree() {
+        let event = rescan(summarize(&[
+            create("/repo/node_modules/a/index.js"),
+            create("/repo/node_modules/b/index.js"),
+            create("/repo/node_modules/b/lib/util.js"),
+        ]));
+
+        assert_eq!(event.path, PathBuf::from("/repo/node_modules"));
+        assert_eq!(event.count, 3);
+    }
+
+    #[test]
+    fn test_summarize_includes_rename_targets() {
+        let event = rescan(summarize(&[
+            create("/repo/src/a.rs"),
+            EventType::Rename(RenameEvent::new(
+                PathBuf::from("/repo/src/b.rs"),
+                PathBuf::from("/repo/tests/b.rs"),
+                false,
+                Vec::new(),
+            )),
+        ]));
+
+        assert_eq!(event.path, PathBuf::from("/repo"));
+    }
+
+    #[test]
+    fn test_summarize_single_path() {
+        let event = rescan(summarize(&[create("/repo/a.txt"), create("/repo/a.txt")]));
+
+        assert_eq!(event.path, PathBuf::from("/repo/a.txt"));
+    }
+}
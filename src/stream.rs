@@ -0,0 +1,168 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+
+use crate::channel::{BatchReceiver, RecvError};
+use crate::error::WatchError;
+use crate::events::EventType;
+
+type Recv = Pin<Box<dyn Future<Output = (BatchReceiver, Result<Vec<EventType>, RecvError>)> + Send>>;
+
+/// Wait for the next batch, giving the receiver back along with it, so the future doesn't borrow the stream.
+fn recv(mut rx: BatchReceiver) -> Recv {
+    Box::pin(async move {
+        let batch = rx.recv().await;
+
+        (rx, batch)
+    })
+}
+
+/// Event batches of a subscription as a `Stream`.
+///
+/// Batches dropped because the consumer fell behind are reported as `WatchError::Lagged`
+/// (with the `drop_oldest` and `raise` backpressure policies),
+/// after which the stream goes on with the batches still buffered.
+/// Consumers that don't care about lost batches can skip the errors, e.g. with `filter_map(Result::ok)`.
+/// The stream ends once the subscription is stopped.
+pub struct EventStream {
+    recv: Option<Recv>,
+}
+
+impl EventStream {
+    pub fn new(rx: BatchReceiver) -> Self {
+        Self { recv: Some(recv(rx)) }
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Result<Vec<EventType>, WatchError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let Some(ref mut pending) = self.recv else {
+            return Poll::Ready(None);
+        };
+
+        let (rx, batch) = match pending.as_mut().poll(cx) {
+            Poll::Ready(result) => result,
+            Poll::Pending => return Poll::Pending,
+        };
+
+        match batch {
+            Ok(batch) => {
+                self.recv = Some(recv(rx));
+                Poll::Ready(Some(Ok(batch)))
+            }
+            Err(RecvError::Lagged(n)) => {
+                self.recv = Some(recv(rx));
+                Poll::Ready(Some(Err(WatchError::Lagged(n))))
+            }
+            Err(RecvError::Closed) => {
+                self.recv = None;
+                Poll::Ready(None)
+            }
+        }
+    }
+}
+
+/// Event batches of a subscription as a blocking `Iterator`, for consumers outside of async code.
+///
+/// Lagged batches are reported the same way as by `EventStream`.
+/// Iterating within an async execution context panics.
+pub struct BlockingEvents {
+    rx: Option<BatchReceiver>,
+}
+
+impl BlockingEvents {
+    pub fn new(rx: BatchReceiver) -> Self {
+        Self { rx: Some(rx) }
+    }
+}
+
+impl Iterator for BlockingEvents {
+    type Item = Result<Vec<EventType>, WatchError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rx = self.rx.as_mut()?;
+
+        match rx.blocking_recv() {
+            Ok(batch) => Some(Ok(batch)),
+            Err(RecvError::Lagged(n)) => Some(Err(WatchError::Lagged(n))),
+            Err(RecvError::Closed) => {
+                self.rx = None;
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use tokio_stream::StreamExt;
+
+    use crate::channel::{BackpressurePolicy, channel};
+    use crate::events::base::ObjectType;
+    use crate::events::create::CreateEvent;
+
+    fn create(path: &str) -> EventType {
+        EventType::Create(CreateEvent::new(PathBuf::from(path), ObjectType::File))
+    }
+
+    #[tokio::test]
+    async fn test_stream_reports_lagged_batches() {
+        let (tx, rx) = channel(BackpressurePolicy::Raise, 1);
+
+        tx.send(vec![create("/a")]).await;
+        tx.send(vec![create("/b")]).await;
+        drop(tx);
+
+        let items: Vec<_> = rx.into_stream().collect().await;
+
+        assert_eq!(items.len(), 2);
+        assert!(matches!(items[0], Err(WatchError::Lagged(1))));
+        assert_eq!(
+            items[1].as_ref().unwrap()[0].path(),
+            Some(PathBuf::from("/b").as_path())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_combinators() {
+        let (tx, rx) = channel(BackpressurePolicy::Block, 4);
+
+        tx.send(vec![create("/a"), create("/b")]).await;
+        tx.send(vec![create("/c")]).await;
+        drop(tx);
+
+        let sizes: Vec<usize> = rx
+            .into_stream()
+            .filter_map(Result::ok)
+            .map(|batch| batch.len())
+            .collect()
+            .await;
+
+        assert_eq!(sizes, vec![2, 1]);
+    }
+
+    #[test]
+    fn test_blocking_iter() {
+        let (tx, rx) = channel(BackpressurePolicy::Coalesce, 4);
+
+        let sender = std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+            runtime.block_on(tx.send(vec![create("/a")]));
+        });
+
+        let batches: Vec<_> = rx.into_blocking_iter().collect();
+        sender.join().unwrap();
+
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].as_ref().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,445 @@
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use tokio::{
+    sync::{Notify, oneshot},
+    time,
+};
+
+use crate::aggregate::aggregate;
+use crate::channel::{BackpressurePolicy, BatchReceiver, BatchSender, channel};
+use crate::collapse::collapse;
+use crate::events::EventType;
+use crate::file_cache::FileCache;
+use crate::filter::EventFilter;
+use crate::processor::{DeliveryOptions, EventProcessor, RawEvent, SharedProcessor};
+use crate::runtime;
+use crate::storm;
+use crate::suppress::Suppressor;
+use crate::watcher::create_event;
+
+/// The name of the subscription every watcher starts with.
+pub(crate) const DEFAULT_SUBSCRIPTION: &str = "default";
+
+/// Post-processing applied to every batch before delivery.
+#[derive(Debug, Default)]
+pub struct Pipeline {
+    pub event_filter: Option<EventFilter>,
+    /// Reduce events of each path to a canonical summary
+    pub collapse: bool,
+    /// Split batches into several batches of at most this many events.
+    ///
+    /// The batches of one split are sent as fast as the consumer receives them (even with `drop_oldest` or `raise`),
+    /// so a split never pushes its own batches out of the buffer.
+    pub max_batch_size: Option<usize>,
+    /// Replace batches of more than this many events with a single rescan event.
+    ///
+    /// Ready events are held back for one drain and delivered along with the ones that become ready by then
+    /// (unless events are delivered as soon as they arrive), so a burst isn't split into batches below the threshold.
+    /// The same goes for `aggregate_threshold`.
+    pub storm_threshold: Option<usize>,
+    /// Set by the watcher when directory renames are expanded into their children
+    pub(crate) file_cache: Option<Arc<Mutex<FileCache>>>,
+    /// Roll up events of directories with more than this many changed direct children into one event per directory
+    pub aggregate_threshold: Option<usize>,
+    /// Ready events held back for one drain (see `storm_threshold`)
+    pub(crate) held: Mutex<Vec<RawEvent>>,
+}
+
+impl Pipeline {
+    /// Returns events of the processor that are ready for delivery at the given time.
+    ///
+    /// With a storm or aggregate threshold and `hold` set, ready events are held back until the next drain
+    /// and delivered along with the ones that become ready by then (see `storm_threshold`).
+    /// The processor is always drained at the given time, so renames are paired and paths debounced as usual.
+    pub(crate) fn ready_events(&self, processor: &mut dyn EventProcessor, now: Instant, hold: bool) -> Vec<RawEvent> {
+        let events = processor.get_events_at(now);
+
+        let mut held = self.held.lock().unwrap_or_else(PoisonError::into_inner);
+        let thresholds = self.storm_threshold.is_some() || self.aggregate_threshold.is_some();
+
+        if held.is_empty() && thresholds && hold {
+            *held = events;
+
+            return Vec::new();
+        }
+
+        let mut ready = std::mem::take(&mut *held);
+        ready.extend(events);
+
+        ready
+    }
+
+    /// Returns the held back events along with all pending events of the processor,
+    /// no matter how long they have been buffered.
+    pub(crate) fn flush_events(&self, processor: &mut dyn EventProcessor) -> Vec<RawEvent> {
+        let mut events = std::mem::take(&mut *self.held.lock().unwrap_or_else(PoisonError::into_inner));
+        events.extend(processor.flush_events());
+
+        events
+    }
+
+    /// Whether ready events are held back until the next drain
+    pub(crate) fn is_holding(&self) -> bool {
+        !self.held.lock().unwrap_or_else(PoisonError::into_inner).is_empty()
+    }
+
+    /// Turn events released by the processor into a batch: convert them to notifykit events,
+    /// filter them and apply the rest of the pipeline.
+    pub(crate) fn process(&self, raw: Vec<RawEvent>) -> Vec<EventType> {
+        let file_cache = self.file_cache.as_ref().and_then(|cache| cache.lock().ok());
+
+        self.process_with(raw, file_cache.as_deref())
+    }
+
+    /// The same as `process()` with the given file cache instead of the watcher's one (see `Replay`).
+    pub(crate) fn process_with(&self, raw: Vec<RawEvent>, file_cache: Option<&FileCache>) -> Vec<EventType> {
+        let mut batch = Vec::with_capacity(raw.len());
+        for r in raw {
+            if let Some(mut ev) = create_event(&r, file_cache) {
+                if let (EventType::Rename(rename), Some(cache)) = (&mut ev, file_cache) {
+                    if rename.is_dir {
+                        rename.children = cache.renamed_children(&rename.old_path, &rename.new_path);
+                    }
+                }
+
+                if let Some(ref filter) = self.event_filter {
+                    if !filter.should_filter(&ev) {
+                        batch.push(ev);
+                    }
+                } else {
+                    batch.push(ev);
+                }
+            }
+        }
+
+        let batch = if self.collapse { collapse(batch) } else { batch };
+
+        let batch = match self.aggregate_threshold {
+            Some(threshold) => aggregate(batch, threshold),
+            None => batch,
+        };
+
+        match self.storm_threshold {
+            Some(threshold) if batch.len() > threshold => vec![storm::summarize(&batch)],
+            _ => batch,
+        }
+    }
+
+    /// Split the batch into batches of at most the max batch size.
+    ///
+    /// An empty batch isn't delivered at all, so there's nothing left of it.
+    pub(crate) fn split(&self, batch: Vec<EventType>) -> Vec<Vec<EventType>> {
+        match self.max_batch_size {
+            _ if batch.is_empty() => Vec::new(),
+            Some(size) if size > 0 && batch.len() > size => {
+                let mut chunks = Vec::with_capacity(batch.len().div_ceil(size));
+                let mut events = batch.into_iter();
+
+                while events.len() > 0 {
+                    chunks.push(events.by_ref().take(size).collect());
+                }
+
+                chunks
+            }
+            _ => vec![batch],
+        }
+    }
+
+    /// Send the batch to the channel, split according to the max batch size.
+    ///
+    /// Every batch of the split but the first waits for room in the channel.
+    async fn deliver(&self, tx: &BatchSender, batch: Vec<EventType>) {
+        for (index, chunk) in self.split(batch).into_iter().enumerate() {
+            if index > 0 {
+                tx.wait_for_room().await;
+            }
+
+            tx.send(chunk).await;
+        }
+    }
+}
+
+/// The part of a subscription the backend callback feeds raw events into.
+#[derive(Debug, Clone)]
+pub(crate) struct SubscriptionInput {
+    pub processor: SharedProcessor,
+    /// Wakes up the drain loop right away for delivery modes that release events as soon as they arrive
+    pub wakeup: Option<Arc<Notify>>,
+}
+
+/// An independent consumer of the watcher's events.
+///
+/// Every subscription buffers raw events in its own processor and delivers them
+/// through its own channel, so consumers with different filters and debouncing settings
+/// can share one backend watcher.
+#[derive(Debug)]
+pub(crate) struct Subscription {
+    event_buffer_size: usize,
+    backpressure: BackpressurePolicy,
+    processor: SharedProcessor,
+    wakeup: Option<Arc<Notify>>,
+    tx: BatchSender,
+    rx: Option<BatchReceiver>,
+    stop_tx: Option<oneshot::Sender<bool>>,
+    drain_handle: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl Subscription {
+    pub fn new(
+        delivery: DeliveryOptions,
+        event_buffer_size: usize,
+        backpressure: BackpressurePolicy,
+        suppressor: Arc<Mutex<Suppressor>>,
+    ) -> Self {
+        let (tx, rx) = Self::channel(backpressure, event_buffer_size);
+
+        Self {
+            event_buffer_size,
+            backpressure,
+            processor: delivery.suppressing_processor(suppressor),
+            wakeup: delivery.is_immediate().then(|| Arc::new(Notify::new())),
+            tx,
+            rx,
+            stop_tx: None,
+            drain_handle: None,
+        }
+    }
+
+    pub fn input(&self) -> SubscriptionInput {
+        SubscriptionInput {
+            processor: Arc::clone(&self.processor),
+            wakeup: self.wakeup.clone(),
+        }
+    }
+
+    /// Returns a receiver of the subscription's events.
+    ///
+    /// Channels that support only one receiver are recreated if their receiver was already handed out,
+    /// so the drain task has to be (re)started after that.
+    pub fn subscribe(&mut self) -> BatchReceiver {
+        if let Some(rx) = self.rx.take().or_else(|| self.tx.subscribe(self.backpressure)) {
+            return rx;
+        }
+
+        let (tx, rx) = channel(self.backpressure, self.event_buffer_size);
+        self.tx = tx;
+
+        rx
+    }
+
+    /// Broadcast channels hand out receivers on demand, so their initial receiver is not kept around.
+    fn channel(backpressure: BackpressurePolicy, event_buffer_size: usize) -> (BatchSender, Option<BatchReceiver>) {
+        let (tx, rx) = channel(backpressure, event_buffer_size);
+
+        match tx {
+            BatchSender::Broadcast(..) => (tx, None),
+            _ => (tx, Some(rx)),
+        }
+    }
+
+    /// Stop delivering events and close the event stream.
+    ///
+    /// When `flush` is set, events that are still buffered are delivered as the final batch before the stream is closed.
+    pub fn stop(&mut self, flush: bool) {
+        if let Some(tx) = self.stop_tx.take() {
+            let _ = tx.send(flush);
+        }
+
+        if let Some(handle) = self.drain_handle.take() {
+            // the drain task exits by itself once the final batch is sent
+            if !flush {
+                handle.abort();
+            }
+        }
+
+        (self.tx, self.rx) = Self::channel(self.backpressure, self.event_buffer_size);
+    }
+
+    pub fn start_drain(&mut self, debounce_delay: Duration, pipeline: Pipeline, debug: bool) {
+        if let Some(tx) = self.stop_tx.take() {
+            let _ = tx.send(false);
+        }
+
+        if let Some(handle) = self.drain_handle.take() {
+            handle.abort();
+        }
+
+        let (stop_tx, mut stop_rx) = oneshot::channel();
+        self.stop_tx = Some(stop_tx);
+
+        let proc = Arc::clone(&self.processor);
+        let wakeup = self.wakeup.clone();
+        // events released as soon as they arrive can't be held back
+        let ready = Drain::Ready { hold: wakeup.is_none() };
+        let tx = self.tx.clone();
+
+        self.drain_handle = Some(runtime::runtime().spawn(async move {
+            let mut ticker = time::interval(debounce_delay);
+
+            loop {
+                let woken_up = async {
+                    match wakeup {
+                        Some(ref wakeup) => wakeup.notified().await,
+                        None => std::future::pending().await,
+                    }
+                };
+
+                tokio::select! {
+                    flush = &mut stop_rx => {
+                        if flush.unwrap_or_default() {
+                            let batch = drain_batch(&proc, &pipeline, debug, Drain::Flush);
+                            pipeline.deliver(&tx, batch).await;
+                        }
+
+                        break;
+                    },
+                    _ = ticker.tick() => {
+                        let batch = drain_batch(&proc, &pipeline, debug, ready);
+                        pipeline.deliver(&tx, batch).await;
+                    },
+                    _ = woken_up => {
+                        let batch = drain_batch(&proc, &pipeline, debug, ready);
+                        pipeline.deliver(&tx, batch).await;
+                    }
+                }
+            }
+        }));
+    }
+}
+
+/// What a drain takes out of the processor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Drain {
+    /// Events ready for delivery, which may be held back for one drain (see `Pipeline::ready_events()`)
+    Ready { hold: bool },
+    /// All pending events, regardless of how long they have been buffered
+    Flush,
+}
+
+/// Pull processed events out of the processor and turn them into a batch ready for delivery.
+fn drain_batch(proc: &Mutex<dyn EventProcessor>, pipeline: &Pipeline, debug: bool, drain: Drain) -> Vec<EventType> {
+    let (raw, errs) = {
+        let mut p = match proc.lock() {
+            Ok(guard) => guard,
+            Err(e) => {
+                eprintln!("notifykit: event processor lock poisoned, skipping drain tick: {e}");
+                return Vec::new();
+            }
+        };
+
+        let raw = match drain {
+            Drain::Ready { hold } => pipeline.ready_events(&mut *p, Instant::now(), hold),
+            Drain::Flush => pipeline.flush_events(&mut *p),
+        };
+
+        (raw, p.get_errors())
+    };
+
+    if debug && !raw.is_empty() {
+        eprintln!("processed: {:?}", raw);
+    }
+    if !errs.is_empty() {
+        eprintln!("errors: {:?}", errs);
+    }
+    if raw.is_empty() {
+        return Vec::new();
+    }
+
+    pipeline.process(raw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use crate::events::base::ObjectType;
+    use crate::events::create::CreateEvent;
+    use notify::event::{ModifyKind, RenameMode};
+
+    fn create(path: &str) -> EventType {
+        EventType::Create(CreateEvent::new(PathBuf::from(path), ObjectType::File))
+    }
+
+    #[tokio::test]
+    async fn test_deliver_splits_large_batches() {
+        let (tx, mut rx) = channel(BackpressurePolicy::Block, 8);
+        let pipeline = Pipeline {
+            max_batch_size: Some(2),
+            ..Pipeline::default()
+        };
+
+        pipeline
+            .deliver(
+                &tx,
+                vec![create("/a"), create("/b"), create("/c"), create("/d"), create("/e")],
+            )
+            .await;
+        drop(tx);
+
+        let mut sizes = Vec::new();
+        while let Ok(batch) = rx.recv().await {
+            sizes.push(batch.len());
+        }
+
+        assert_eq!(sizes, vec![2, 2, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_deliver_paces_split_batches() {
+        // fewer batches fit into the buffer than the split makes
+        let (tx, mut rx) = channel(BackpressurePolicy::DropOldest, 2);
+        let pipeline = Pipeline {
+            max_batch_size: Some(1),
+            ..Pipeline::default()
+        };
+
+        let batch: Vec<EventType> = (0..5).map(|i| create(&format!("/{i}"))).collect();
+        let delivered = tokio::spawn(async move { pipeline.deliver(&tx, batch).await });
+
+        let mut paths = Vec::new();
+        while let Ok(batch) = rx.recv().await {
+            paths.extend(batch.iter().filter_map(|e| e.path().map(|p| p.to_path_buf())));
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        delivered.await.unwrap();
+
+        assert_eq!(
+            paths,
+            (0..5).map(|i| PathBuf::from(format!("/{i}"))).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_process_takes_rename_type_from_cache() {
+        let root = std::env::temp_dir().join(format!("notifykit-subscription-rename-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("old")).unwrap();
+
+        let mut cache = FileCache::new();
+        cache.add_root(&root, notify::RecursiveMode::Recursive);
+
+        let rename = notify::Event::new(notify::EventKind::Modify(ModifyKind::Name(RenameMode::Both)))
+            .add_path(root.join("old"))
+            .add_path(root.join("new"));
+
+        std::fs::rename(root.join("old"), root.join("new")).unwrap();
+        cache.handle_event(&rename);
+        // gone by the time the event is drained
+        std::fs::remove_dir(root.join("new")).unwrap();
+
+        let pipeline = Pipeline {
+            file_cache: Some(Arc::new(Mutex::new(cache))),
+            ..Pipeline::default()
+        };
+
+        let batch = pipeline.process(vec![rename.into()]);
+
+        assert!(matches!(&batch[..], [EventType::Rename(e)] if e.is_dir));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
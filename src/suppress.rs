@@ -0,0 +1,328 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use notify::{Error as NotifyError, Event as NotifyEvent};
+
+use crate::processor::{EventProcessor, RawEvent};
+
+/// How long after a suppression window is closed events are still considered self-inflicted.
+///
+/// Backends deliver events with some latency, so writes done right before the window is closed
+/// may be reported slightly after that.
+const SUPPRESS_GRACE: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+struct SuppressWindow {
+    id: u64,
+    paths: Vec<PathBuf>,
+    start: Instant,
+    end: Option<Instant>,
+}
+
+impl SuppressWindow {
+    fn covers(&self, path: &Path, time: Instant) -> bool {
+        if time < self.start {
+            return false;
+        }
+
+        if let Some(end) = self.end {
+            if time > end + SUPPRESS_GRACE {
+                return false;
+            }
+        }
+
+        self.paths.iter().any(|p| path.starts_with(p))
+    }
+}
+
+/// Resolve the path the way the backend reports it (absolute, with symlinks resolved).
+///
+/// Paths that don't exist yet are resolved through their closest existing ancestor,
+/// so files about to be created are matched too.
+fn canonicalize(path: &Path) -> PathBuf {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+
+    for ancestor in path.ancestors() {
+        if let Ok(canonical) = ancestor.canonicalize() {
+            let rest = path.strip_prefix(ancestor).unwrap_or(Path::new(""));
+
+            return match rest.as_os_str().is_empty() {
+                true => canonical,
+                false => canonical.join(rest),
+            };
+        }
+    }
+
+    path
+}
+
+/// Keeps track of paths the application is about to modify itself,
+/// so events caused by its own writes can be dropped before delivery.
+///
+/// Suppression is based on the time an event was received from the backend
+/// and events are checked before they're buffered (see `SuppressingProcessor`),
+/// so events that are still buffered when the window is closed are dropped as well.
+#[derive(Debug)]
+pub(crate) struct Suppressor {
+    windows: Vec<SuppressWindow>,
+    next_id: u64,
+    retention: Duration,
+}
+
+impl Suppressor {
+    /// `retention` defines how long closed windows are kept around
+    /// and should be longer than the time events are held back before they're checked
+    /// (e.g. the first half of a rename waiting for the second one).
+    pub fn new(retention: Duration) -> Self {
+        Self {
+            windows: Vec::new(),
+            next_id: 0,
+            retention,
+        }
+    }
+
+    /// Open a suppression window for the given paths (directories cover all their children).
+    ///
+    /// The window stays open until `release()` is called with the returned ID.
+    pub fn suppress(&mut self, paths: Vec<PathBuf>) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.windows.push(SuppressWindow {
+            id,
+            paths: paths.iter().map(|path| canonicalize(path)).collect(),
+            start: Instant::now(),
+            end: None,
+        });
+
+        id
+    }
+
+    /// Open a suppression window that closes by itself after `timeout`.
+    pub fn expect_write(&mut self, paths: Vec<PathBuf>, timeout: Duration) {
+        let start = Instant::now();
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.windows.push(SuppressWindow {
+            id,
+            paths: paths.iter().map(|path| canonicalize(path)).collect(),
+            start,
+            end: Some(start + timeout),
+        });
+    }
+
+    /// Close the suppression window. Events received during the window are still going to be dropped.
+    pub fn release(&mut self, id: u64) {
+        let now = Instant::now();
+
+        if let Some(window) = self.windows.iter_mut().find(|w| w.id == id) {
+            window.end = Some(now);
+        }
+    }
+
+    /// Keep closed windows for at least `retention`, e.g. when a subscription with a longer drain interval is added.
+    pub fn extend_retention(&mut self, retention: Duration) {
+        self.retention = self.retention.max(retention);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    /// Returns `true` if the event received at `time` for all the `paths` should be **dropped**.
+    pub fn is_suppressed(&self, paths: &[PathBuf], time: Instant) -> bool {
+        if paths.is_empty() {
+            return false;
+        }
+
+        paths
+            .iter()
+            .all(|path| self.windows.iter().any(|w| w.covers(path, time)))
+    }
+
+    /// Forget about windows that were closed long enough ago to not match any events that are yet to be checked.
+    pub fn prune(&mut self) {
+        let now = Instant::now();
+        let retention = self.retention + SUPPRESS_GRACE;
+
+        self.windows
+            .retain(|w| w.end.is_none_or(|end| now.saturating_duration_since(end) < retention));
+    }
+}
+
+/// Drops suppressed events before they're buffered by the wrapped processor,
+/// so they're matched against the suppression windows as they arrive, however long the processor holds events.
+#[derive(Debug)]
+pub(crate) struct SuppressingProcessor<P> {
+    inner: P,
+    suppressor: Arc<Mutex<Suppressor>>,
+}
+
+impl<P: EventProcessor> SuppressingProcessor<P> {
+    pub fn new(inner: P, suppressor: Arc<Mutex<Suppressor>>) -> Self {
+        Self { inner, suppressor }
+    }
+}
+
+impl<P: EventProcessor> EventProcessor for SuppressingProcessor<P> {
+    fn get_events_at(&mut self, now: Instant) -> Vec<RawEvent> {
+        self.inner.get_events_at(now)
+    }
+
+    fn flush_events(&mut self) -> Vec<RawEvent> {
+        self.inner.flush_events()
+    }
+
+    fn get_errors(&mut self) -> Vec<NotifyError> {
+        self.inner.get_errors()
+    }
+
+    fn add_event_at(&mut self, event: NotifyEvent, time: Instant) {
+        match self.suppressor.lock() {
+            Ok(mut suppressor) if !suppressor.is_empty() => {
+                suppressor.prune();
+
+                if suppressor.is_suppressed(&event.paths, time) {
+                    return;
+                }
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("notifykit: suppressor lock poisoned, skipping suppression: {e}"),
+        }
+
+        self.inner.add_event_at(event, time);
+    }
+
+    fn add_error(&mut self, error: NotifyError) {
+        self.inner.add_error(error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_window_suppresses_children() {
+        let mut s = Suppressor::new(Duration::from_secs(1));
+        s.suppress(vec![PathBuf::from("/proj/gen")]);
+
+        let now = Instant::now();
+
+        assert!(s.is_suppressed(&[PathBuf::from("/proj/gen/out.py")], now));
+        assert!(!s.is_suppressed(&[PathBuf::from("/proj/src/main.py")], now));
+    }
+
+    #[test]
+    fn test_events_before_window_are_kept() {
+        let before = Instant::now();
+
+        let mut s = Suppressor::new(Duration::from_secs(1));
+        s.suppress(vec![PathBuf::from("/proj/gen")]);
+
+        assert!(!s.is_suppressed(&[PathBuf::from("/proj/gen/out.py")], before));
+    }
+
+    #[test]
+    fn test_released_window() {
+        let mut s = Suppressor::new(Duration::from_secs(1));
+        let id = s.suppress(vec![PathBuf::from("/proj/gen")]);
+        let during = Instant::now();
+
+        s.release(id);
+
+        assert!(s.is_suppressed(&[PathBuf::from("/proj/gen/out.py")], during));
+        assert!(!s.is_suppressed(
+            &[PathBuf::from("/proj/gen/out.py")],
+            Instant::now() + SUPPRESS_GRACE * 2
+        ));
+    }
+
+    #[test]
+    fn test_rename_needs_both_paths_suppressed() {
+        let mut s = Suppressor::new(Duration::from_secs(1));
+        s.suppress(vec![PathBuf::from("/proj/gen")]);
+
+        let now = Instant::now();
+
+        assert!(!s.is_suppressed(&[PathBuf::from("/proj/gen/a.py"), PathBuf::from("/proj/src/a.py")], now));
+        assert!(s.is_suppressed(&[PathBuf::from("/proj/gen/a.py"), PathBuf::from("/proj/gen/b.py")], now));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_paths_are_canonicalized() {
+        let root = std::env::temp_dir().join(format!("notifykit-suppress-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("real")).unwrap();
+        std::os::unix::fs::symlink(root.join("real"), root.join("link")).unwrap();
+
+        let real = root.join("real").canonicalize().unwrap();
+
+        let mut s = Suppressor::new(Duration::from_secs(1));
+        // neither the file nor its directory exist yet
+        s.suppress(vec![root.join("link/gen/out.py")]);
+
+        assert!(s.is_suppressed(&[real.join("gen/out.py")], Instant::now()));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_suppressing_processor() {
+        use crate::processor::{DebounceProcessor, DeliveryMode, DeliveryOptions};
+        use notify::EventKind;
+
+        let suppressor = Arc::new(Mutex::new(Suppressor::new(Duration::ZERO)));
+        let mut p = SuppressingProcessor::new(
+            DebounceProcessor::new(Duration::from_secs(10), None),
+            suppressor.clone(),
+        );
+
+        let id = suppressor.lock().unwrap().suppress(vec![PathBuf::from("/proj/gen")]);
+        let write = |path: &str| NotifyEvent::new(EventKind::Any).add_path(PathBuf::from(path));
+
+        p.add_event_at(write("/proj/gen/out.py"), Instant::now());
+        p.add_event_at(write("/proj/src/main.py"), Instant::now());
+        suppressor.lock().unwrap().release(id);
+
+        // the window is long gone by the time the events are released
+        std::thread::sleep(SUPPRESS_GRACE + Duration::from_millis(10));
+        suppressor.lock().unwrap().prune();
+
+        let events = p.flush_events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].paths, vec![PathBuf::from("/proj/src/main.py")]);
+
+        // the processors of subscriptions check events the same way
+        let options = DeliveryOptions {
+            mode: DeliveryMode::Debounce,
+            ..DeliveryOptions::default()
+        };
+        let p = options.suppressing_processor(suppressor.clone());
+        suppressor.lock().unwrap().suppress(vec![PathBuf::from("/proj/gen")]);
+        p.lock().unwrap().add_event(write("/proj/gen/out.py"));
+
+        assert!(p.lock().unwrap().flush_events().is_empty());
+    }
+
+    #[test]
+    fn test_prune_keeps_open_windows() {
+        let mut s = Suppressor::new(Duration::ZERO);
+        let id = s.suppress(vec![PathBuf::from("/proj/gen")]);
+        s.expect_write(vec![PathBuf::from("/proj/out.txt")], Duration::ZERO);
+
+        std::thread::sleep(SUPPRESS_GRACE + Duration::from_millis(10));
+        s.prune();
+
+        assert_eq!(s.windows.len(), 1);
+
+        s.release(id);
+        std::thread::sleep(SUPPRESS_GRACE + Duration::from_millis(10));
+        s.prune();
+
+        assert!(s.is_empty());
+    }
+}
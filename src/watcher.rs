@@ -1,78 +1,174 @@
-use std::io::ErrorKind as IOErrorKind;
+use std::collections::HashMap;
+use std::fmt;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::Duration;
 
+use tokio::sync::mpsc;
+
+use crate::channel::{BackpressurePolicy, BatchReceiver};
+use crate::error::WatchError;
 use crate::events::EventType;
 use crate::events::access::from_access_kind;
 use crate::events::create::from_create_kind;
 use crate::events::delete::from_delete_kind;
 use crate::events::modify::{ModifyOtherEvent, ModifyUnknownEvent, from_data_kind, from_metadata_kind};
+use crate::events::moved::{MovedInEvent, MovedOutEvent};
 use crate::events::rename::from_rename_mode;
-use notify::event::ModifyKind;
-use notify::{
-    ErrorKind as NotifyErrorKind, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher as NotifyWatcher,
-};
-use pyo3::exceptions::{PyException, PyFileNotFoundError, PyOSError, PyPermissionError};
-use pyo3::prelude::*;
-use tokio::{
-    sync::{broadcast, oneshot},
-    time,
-};
-// use crate::file_cache::FileCache;
-use crate::filter::EventFilter;
-use crate::processor::{BatchProcessor, EventProcessor, RawEvent};
-
-pyo3::create_exception!(_inotify_toolkit_lib, WatcherError, PyException);
+use crate::file_cache::{CacheOptions, FileCache, FileMetadataCache};
+use crate::processor::{DeliveryOptions, RENAME_PAIRING_TIMEOUT, RawEvent};
+use crate::replay::Recorder;
+use crate::subscription::{DEFAULT_SUBSCRIPTION, Pipeline, Subscription, SubscriptionInput};
+use crate::suppress::Suppressor;
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher as NotifyWatcher};
+
+/// Raw event inputs of all subscriptions, shared with the backend callback.
+type SubscriptionInputs = Arc<RwLock<HashMap<String, SubscriptionInput>>>;
+
+/// Receive backend events as is, returning `false` once they aren't needed anymore.
+type BackendEventTap = Box<dyn FnMut(&Event) -> bool + Send>;
+
+/// Receivers of backend events (see `Watcher::backend_events()` and `Watcher::record()`),
+/// shared with the backend callback.
+#[derive(Clone, Default)]
+struct BackendEventTaps(Arc<Mutex<Vec<BackendEventTap>>>);
+
+impl fmt::Debug for BackendEventTaps {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let taps = self.0.lock().map(|taps| taps.len()).unwrap_or_default();
+
+        f.debug_struct("BackendEventTaps").field("taps", &taps).finish()
+    }
+}
+
+/// Settings of the watcher and its default subscription
+#[derive(Debug, Copy, Clone)]
+pub struct WatcherOptions {
+    /// How the default subscription buffers events before delivery
+    pub delivery: DeliveryOptions,
+    /// How many event batches the default subscription keeps for a consumer that falls behind
+    pub event_buffer_size: usize,
+    /// Print backend events, processed events and watched paths to stderr
+    pub debug: bool,
+    pub follow_symlinks: bool,
+    pub backpressure: BackpressurePolicy,
+    /// Report old and new paths of the children of renamed directories
+    pub expand_dir_renames: bool,
+    /// Report a delete event for every child of deleted directories
+    pub expand_dir_deletes: bool,
+    /// Drop metadata events that don't change anything worth reporting (see `FileCache::is_noise()`)
+    /// for all subscriptions
+    pub ignore_noise: bool,
+}
+
+impl Default for WatcherOptions {
+    fn default() -> Self {
+        Self {
+            delivery: DeliveryOptions::default(),
+            event_buffer_size: 1024,
+            debug: false,
+            follow_symlinks: true,
+            backpressure: BackpressurePolicy::default(),
+            expand_dir_renames: false,
+            expand_dir_deletes: false,
+            ignore_noise: false,
+        }
+    }
+}
 
 #[derive(Debug)]
-pub(crate) struct Watcher {
+pub struct Watcher {
     debug: bool,
-    event_buffer_size: usize,
-    inner: RecommendedWatcher,
-    // file_cache: FileCache,
-    processor: Arc<Mutex<BatchProcessor>>, // TODO: use the EventProcessor trait instead
-    tx: broadcast::Sender<Vec<EventType>>,
-    stop_tx: Option<oneshot::Sender<()>>,
-    drain_handle: Option<tokio::task::JoinHandle<()>>,
+    inner: Option<RecommendedWatcher>,
+    watched_paths: Vec<(PathBuf, RecursiveMode)>,
+    /// Tracks watched paths to expand directory renames into their children and to catch up on dropped events
+    file_cache: Option<Arc<Mutex<FileCache>>>,
+    inputs: SubscriptionInputs,
+    subscriptions: HashMap<String, Subscription>,
+    suppressor: Arc<Mutex<Suppressor>>,
+    backend_event_taps: BackendEventTaps,
 }
 
 impl Watcher {
-    pub fn new(
-        buffering_time_ms: u64,
-        event_buffer_size: usize,
-        debug: bool,
-        follow_symlinks: bool,
-    ) -> Result<Self, notify::Error> {
-        // TODO: hide usage of file cache from Watcher
-        // let file_cache = FileCache::new();
-        // let file_cache_c = file_cache.clone();
+    pub fn new(options: WatcherOptions) -> Result<Self, WatchError> {
+        let WatcherOptions {
+            delivery,
+            event_buffer_size,
+            debug,
+            follow_symlinks,
+            backpressure,
+            expand_dir_renames,
+            expand_dir_deletes,
+            ignore_noise,
+        } = options;
+        let file_cache =
+            (expand_dir_renames || expand_dir_deletes || ignore_noise).then(|| Arc::new(Mutex::new(FileCache::new())));
+        let cache_options = CacheOptions {
+            expand_dir_deletes,
+            ignore_noise,
+        };
+        let file_cache_c = file_cache.clone();
+
+        // closed suppression windows are kept until the rename halves held back during them are checked
+        let suppressor = Arc::new(Mutex::new(Suppressor::new(RENAME_PAIRING_TIMEOUT)));
+
+        // the default subscription starts buffering events right away, so nothing is lost before events() is called
+        let subscription = Subscription::new(delivery, event_buffer_size, backpressure, Arc::clone(&suppressor));
+        let inputs: SubscriptionInputs = Arc::new(RwLock::new(HashMap::from([(
+            DEFAULT_SUBSCRIPTION.to_string(),
+            subscription.input(),
+        )])));
+        let inputs_c = inputs.clone();
 
-        let buffering_duration = Duration::from_millis(buffering_time_ms);
-        let processor = Arc::new(Mutex::new(BatchProcessor::new(buffering_duration)));
-        let processor_c = processor.clone();
+        let rescans = file_cache
+            .as_ref()
+            .and_then(|file_cache| spawn_rescan_worker(Arc::clone(file_cache), inputs.clone()));
 
-        let (tx, _rx) = broadcast::channel::<Vec<EventType>>(event_buffer_size);
+        let backend_event_taps = BackendEventTaps::default();
+        let backend_event_taps_c = backend_event_taps.clone();
 
         let config = notify::Config::default().with_follow_symlinks(follow_symlinks);
 
         let inner = RecommendedWatcher::new(
             move |e: Result<Event, notify::Error>| {
-                let mut event_processor = match processor_c.lock() {
+                let inputs = match inputs_c.read() {
                     Ok(guard) => guard,
                     Err(e) => {
-                        eprintln!("notifykit: event processor lock poisoned, dropping event: {e}");
+                        eprintln!("notifykit: subscriptions lock poisoned, dropping event: {e}");
                         return;
                     }
                 };
 
                 if debug {
-                    println!("raw event: {:?}", e);
+                    eprintln!("raw event: {:?}", e);
                 }
 
-                match e {
-                    Ok(e) => event_processor.add_event(e),
-                    Err(e) => event_processor.add_error(e),
+                if let (Ok(event), Ok(mut taps)) = (&e, backend_event_taps_c.0.lock()) {
+                    taps.retain_mut(|tap| tap(event));
+                }
+
+                // the cache is updated before events are delivered, so it's up-to-date by the time they're drained
+                let events = match (&file_cache_c, e) {
+                    (Some(file_cache), Ok(event)) => match file_cache.lock() {
+                        Ok(mut cache) => {
+                            // the walk can take a while, so it shouldn't hold up the intake of new events
+                            if let (true, Some(rescans)) = (event.need_rescan(), &rescans) {
+                                let _ = rescans.send(event.paths.first().cloned());
+                            }
+
+                            cache.intake(event, cache_options).into_iter().map(Ok).collect()
+                        }
+                        Err(err) => {
+                            eprintln!("notifykit: file cache lock poisoned, skipping cache update: {err}");
+                            vec![Ok(event)]
+                        }
+                    },
+                    (_, e) => vec![e],
+                };
+
+                for e in events {
+                    dispatch(&inputs, &e);
                 }
             },
             config,
@@ -80,177 +176,426 @@ impl Watcher {
 
         Ok(Self {
             debug,
-            event_buffer_size,
-            inner,
-            processor,
-            tx,
-            stop_tx: None,
-            drain_handle: None,
+            inner: Some(inner),
+            watched_paths: Vec::new(),
+            file_cache,
+            inputs,
+            subscriptions: HashMap::from([(DEFAULT_SUBSCRIPTION.to_string(), subscription)]),
+            suppressor,
+            backend_event_taps,
         })
     }
 
-    pub fn watch(&mut self, paths: &[String], recursive: bool, ignore_perm: bool) -> PyResult<()> {
+    pub fn watch(&mut self, paths: &[impl AsRef<Path>], recursive: bool, ignore_perm: bool) -> Result<(), WatchError> {
         let mode = if recursive {
             RecursiveMode::Recursive
         } else {
             RecursiveMode::NonRecursive
         };
 
-        let mut watcher_paths = self.inner.paths_mut();
+        let inner = self.inner.as_mut().ok_or(WatchError::Closed)?;
+
+        let mut added_paths = Vec::with_capacity(paths.len());
+        let mut watcher_paths = inner.paths_mut();
         for p in paths {
-            let path = PathBuf::from(&p);
+            let path = p.as_ref().to_path_buf();
 
             if !path.exists() {
-                return Err(PyFileNotFoundError::new_err(format!(
+                return Err(WatchError::PathNotFound(format!(
                     "No such file or directory: {}",
-                    p
+                    path.display()
                 )));
             }
 
-            let result = watcher_paths.add(Path::new(&p), mode);
+            let result = watcher_paths.add(&path, mode);
+
+            match result {
+                Ok(_) => added_paths.push(path),
+                Err(err) if !ignore_perm => return Err(err.into()),
+                Err(_) => {}
+            }
+        }
+
+        watcher_paths.commit()?;
 
-            if let Err(err) = result {
-                if !ignore_perm {
-                    return Err(map_notify_error(err));
+        for path in added_paths {
+            if let Some(ref file_cache) = self.file_cache {
+                if let Ok(mut file_cache) = file_cache.lock() {
+                    file_cache.add_root(&path, mode);
                 }
             }
 
-            // self.file_cache.add_root(path, mode);
+            if !self.watched_paths.iter().any(|(p, _)| *p == path) {
+                self.watched_paths.push((path, mode));
+            }
         }
 
-        if let Err(err) = watcher_paths.commit() {
-            return Err(map_notify_error(err));
+        if self.debug {
+            eprintln!("watcher: {:?}", inner);
+        }
+
+        Ok(())
+    }
+
+    pub fn unwatch(&mut self, paths: &[impl AsRef<Path>]) -> Result<(), WatchError> {
+        let inner = self.inner.as_mut().ok_or(WatchError::Closed)?;
+
+        for path in paths {
+            let path = path.as_ref();
+
+            inner.unwatch(path)?;
+
+            self.watched_paths.retain(|(p, _)| p != path);
+
+            if let Some(ref file_cache) = self.file_cache {
+                if let Ok(mut file_cache) = file_cache.lock() {
+                    file_cache.remove_root(path);
+                }
+            }
         }
 
         if self.debug {
-            println!("watcher: {:?}", self.inner);
+            eprintln!("watcher: {:?}", inner);
         }
 
         Ok(())
     }
 
-    pub fn unwatch(&mut self, paths: Vec<String>) -> PyResult<()> {
-        for path_str in paths.into_iter() {
-            let path = Path::new(&path_str);
+    /// Stop delivering events and close the event streams of all subscriptions.
+    ///
+    /// When `flush` is set, events that are still buffered are delivered as the final batch before the streams are closed.
+    /// Named subscriptions are removed, while the default one can be started again.
+    pub fn stop(&mut self, flush: bool) {
+        for subscription in self.subscriptions.values_mut() {
+            subscription.stop(flush);
+        }
 
-            let result = self.inner.unwatch(path);
+        self.subscriptions.retain(|name, _| name == DEFAULT_SUBSCRIPTION);
 
-            if let Err(err) = result {
-                return Err(map_notify_error(err));
+        if let Ok(mut inputs) = self.inputs.write() {
+            inputs.retain(|name, _| name == DEFAULT_SUBSCRIPTION);
+        }
+    }
+
+    /// Stop delivering events, unwatch all paths and shut down the backend, releasing its thread and kernel resources.
+    ///
+    /// The watcher can't be used anymore after it's closed.
+    pub fn close(&mut self) {
+        self.stop(false);
+
+        if let Ok(mut taps) = self.backend_event_taps.0.lock() {
+            taps.clear();
+        }
+
+        if let Some(mut inner) = self.inner.take() {
+            for (path, _) in self.watched_paths.drain(..) {
+                let _ = inner.unwatch(&path);
             }
 
-            // self.file_cache.remove_root(path);
+            if self.debug {
+                eprintln!("watcher closed: {:?}", inner);
+            }
         }
+    }
 
-        if self.debug {
-            println!("watcher: {:?}", self.inner);
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_none()
+    }
+
+    /// Receive every event of the backend as is, before it's debounced, filtered or turned into notifykit events.
+    ///
+    /// Meant for debugging: the events are buffered without a limit until they're received,
+    /// and the stream is closed once the receiver is dropped or the watcher is closed.
+    pub fn backend_events(&mut self) -> Result<mpsc::UnboundedReceiver<Event>, WatchError> {
+        if self.is_closed() {
+            return Err(WatchError::Closed);
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.add_backend_event_tap(Box::new(move |event| tx.send(event.clone()).is_ok()))?;
+
+        Ok(rx)
+    }
+
+    /// Record every event of the backend to the file along with the time it has arrived at,
+    /// until the watcher is closed.
+    ///
+    /// The recording can be fed through the event processor and pipeline again with `Replay`,
+    /// e.g. to reproduce a debouncing issue.
+    /// The file should be outside of the watched paths, otherwise the recording records its own writes.
+    pub fn record(&mut self, path: impl AsRef<Path>) -> Result<(), WatchError> {
+        if self.is_closed() {
+            return Err(WatchError::Closed);
         }
 
+        let mut recorder = Recorder::create(path)?;
+        self.add_backend_event_tap(Box::new(move |event| match recorder.record(event) {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("notifykit: failed to record an event, stopping the recording: {e}");
+                false
+            }
+        }))
+    }
+
+    fn add_backend_event_tap(&mut self, tap: BackendEventTap) -> Result<(), WatchError> {
+        self.backend_event_taps.0.lock()?.push(tap);
+
         Ok(())
     }
 
-    pub fn stop(&mut self) {
-        if let Some(tx) = self.stop_tx.take() {
-            let _ = tx.send(());
+    /// Start delivering events of the default subscription, restarting its drain task if it's already running.
+    pub fn start_drain(&mut self, debounce_delay: Duration, mut pipeline: Pipeline) {
+        pipeline.file_cache = self.file_cache.clone();
+
+        let debug = self.debug;
+
+        self.extend_suppression(debounce_delay);
+
+        if let Some(subscription) = self.subscriptions.get_mut(DEFAULT_SUBSCRIPTION) {
+            subscription.start_drain(debounce_delay, pipeline, debug);
         }
+    }
 
-        if let Some(handle) = self.drain_handle.take() {
-            handle.abort();
+    /// Add a named subscription with its own debouncing, buffer size and filter, and start delivering its events.
+    pub fn add_subscription(
+        &mut self,
+        name: String,
+        delivery: DeliveryOptions,
+        event_buffer_size: usize,
+        debounce_delay: Duration,
+        mut pipeline: Pipeline,
+        backpressure: BackpressurePolicy,
+    ) -> Result<BatchReceiver, WatchError> {
+        if self.subscriptions.contains_key(&name) {
+            return Err(WatchError::SubscriptionExists(name));
         }
 
-        let (new_tx, _rx) = broadcast::channel::<Vec<EventType>>(self.event_buffer_size);
-        self.tx = new_tx;
+        self.extend_suppression(debounce_delay);
+
+        let mut subscription =
+            Subscription::new(delivery, event_buffer_size, backpressure, Arc::clone(&self.suppressor));
+        pipeline.file_cache = self.file_cache.clone();
+
+        let rx = subscription.subscribe();
+        subscription.start_drain(debounce_delay, pipeline, self.debug);
+
+        self.inputs.write()?.insert(name.clone(), subscription.input());
+        self.subscriptions.insert(name, subscription);
+
+        Ok(rx)
     }
 
-    pub fn start_drain(&mut self, debounce_delay: Duration, event_filter: Option<EventFilter>) {
-        if let Some(tx) = self.stop_tx.take() {
-            let _ = tx.send(());
+    /// Stop and remove a named subscription, closing its event stream.
+    pub fn remove_subscription(&mut self, name: &str) -> Result<(), WatchError> {
+        if name == DEFAULT_SUBSCRIPTION {
+            return Err(WatchError::DefaultSubscription);
         }
 
-        if let Some(handle) = self.drain_handle.take() {
-            handle.abort();
+        let mut subscription = self
+            .subscriptions
+            .remove(name)
+            .ok_or_else(|| WatchError::NoSuchSubscription(name.to_string()))?;
+
+        subscription.stop(false);
+
+        self.inputs.write()?.remove(name);
+
+        Ok(())
+    }
+
+    /// Keep closed suppression windows until rename halves held back during them are released by the drain loop,
+    /// which only checks for them once per `debounce_delay`.
+    fn extend_suppression(&self, debounce_delay: Duration) {
+        if let Ok(mut suppressor) = self.suppressor.lock() {
+            suppressor.extend_retention(debounce_delay + RENAME_PAIRING_TIMEOUT);
         }
+    }
 
-        let (stop_tx, mut stop_rx) = oneshot::channel();
-        self.stop_tx = Some(stop_tx);
+    pub fn suppress(&self, paths: Vec<PathBuf>) -> Result<u64, WatchError> {
+        let mut suppressor = self.suppressor.lock()?;
 
-        let proc = Arc::clone(&self.processor);
-        let tx = self.tx.clone();
-        let debug = self.debug;
+        Ok(suppressor.suppress(paths))
+    }
 
-        self.drain_handle = Some(pyo3_async_runtimes::tokio::get_runtime().spawn(async move {
-            let mut ticker = time::interval(debounce_delay);
-
-            loop {
-                tokio::select! {
-                    _ = &mut stop_rx => break,
-                    _ = ticker.tick() => {
-                        let (raw, errs) = {
-                            let mut p = match proc.lock() {
-                                Ok(guard) => guard,
-                                Err(e) => {
-                                    eprintln!("notifykit: event processor lock poisoned, skipping drain tick: {e}");
-                                    continue;
-                                }
-                            };
-                            (p.get_events(), p.get_errors())
-                        };
-                        if debug && !raw.is_empty() { println!("processed: {:?}", raw); }
-                        if !errs.is_empty() { eprintln!("errors: {:?}", errs); }
-                        if raw.is_empty() { continue; }
-
-                        let mut batch = Vec::with_capacity(raw.len());
-                        for r in raw {
-                            if let Some(ev) = create_event(&r) {
-                                if let Some(ref filter) = event_filter {
-                                    if !filter.should_filter(&ev) {
-                                        batch.push(ev);
-                                    }
-                                } else {
-                                    batch.push(ev);
-                                }
-                            }
-                        }
+    pub fn release(&self, id: u64) -> Result<(), WatchError> {
+        let mut suppressor = self.suppressor.lock()?;
 
-                        if !batch.is_empty() { let _ = tx.send(batch); }
-                    }
-                }
-            }
-        }));
+        suppressor.release(id);
+
+        Ok(())
     }
 
-    pub fn subscribe(&self) -> broadcast::Receiver<Vec<EventType>> {
-        self.tx.subscribe()
+    pub fn expect_write(&self, paths: Vec<PathBuf>, timeout: Duration) -> Result<(), WatchError> {
+        let mut suppressor = self.suppressor.lock()?;
+
+        suppressor.expect_write(paths, timeout);
+
+        Ok(())
+    }
+
+    /// Save paths under the watched paths along with their file IDs, sizes and modification times to disk.
+    pub fn save_snapshot(&self, path: &Path) -> Result<(), WatchError> {
+        Ok(self.scan()?.save(path)?)
+    }
+
+    /// Returns events that happened under the watched paths since the snapshot was saved.
+    pub fn changes_since(&self, snapshot_path: &Path) -> Result<Vec<EventType>, WatchError> {
+        let snapshot = FileCache::load(snapshot_path).map_err(WatchError::Snapshot)?;
+
+        Ok(self.scan()?.diff_since(&snapshot))
+    }
+
+    /// Returns the current state of the watched paths, taken from the file cache if it's kept up-to-date.
+    fn scan(&self) -> Result<FileCache, WatchError> {
+        if self.is_closed() {
+            return Err(WatchError::Closed);
+        }
+
+        if let Some(ref file_cache) = self.file_cache {
+            return Ok(file_cache.lock()?.clone());
+        }
+
+        let mut cache = FileCache::new();
+
+        for (path, mode) in &self.watched_paths {
+            cache.add_root(path, *mode);
+        }
+
+        Ok(cache)
+    }
+
+    /// Returns a receiver of the default subscription's events.
+    ///
+    /// The drain task has to be (re)started afterwards, see `Subscription::subscribe()`.
+    /// Use `BatchReceiver::into_stream()` or `BatchReceiver::into_blocking_iter()` to consume the events
+    /// as a `Stream` or an `Iterator`.
+    pub fn subscribe(&mut self) -> BatchReceiver {
+        self.subscriptions
+            .get_mut(DEFAULT_SUBSCRIPTION)
+            .expect("default subscription is never removed")
+            .subscribe()
     }
 
     pub fn repr(&mut self) -> String {
-        format!("Watcher({:#?})", self.inner)
+        match self.inner {
+            Some(ref inner) => format!("Watcher({:#?})", inner),
+            None => "Watcher(closed)".to_string(),
+        }
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.close();
     }
 }
 
-fn map_notify_error(notify_error: notify::Error) -> PyErr {
-    let err_str = notify_error.to_string();
+/// Pass a raw event (or error) to all subscriptions.
+fn dispatch(inputs: &HashMap<String, SubscriptionInput>, e: &Result<Event, notify::Error>) {
+    for input in inputs.values() {
+        let mut event_processor = match input.processor.lock() {
+            Ok(guard) => guard,
+            Err(e) => {
+                eprintln!("notifykit: event processor lock poisoned, dropping event: {e}");
+                continue;
+            }
+        };
+
+        match e {
+            Ok(e) => event_processor.add_event(e.clone()),
+            Err(e) => event_processor.add_error(copy_error(e)),
+        }
 
-    match notify_error.kind {
-        NotifyErrorKind::PathNotFound => return PyFileNotFoundError::new_err(err_str),
-        NotifyErrorKind::Generic(ref err) => {
-            // on Windows, we get a Generic with this message when the path does not exist
-            if err.as_str() == "Input watch path is neither a file nor a directory." {
-                return PyFileNotFoundError::new_err(err_str);
+        if let Some(ref wakeup) = input.wakeup {
+            wakeup.notify_one();
+        }
+    }
+}
+
+/// Start the thread that walks the subtrees the back-end has dropped events for (or all watched paths)
+/// and delivers the changes it missed as synthetic events.
+///
+/// Requests that come in while a walk is running are merged into the next walk.
+/// The changes are delivered once the walk is over, so they come after the events the back-end has reported
+/// in the meantime. Those events have updated the cache already, so they aren't repeated or undone
+/// by the rescan (see `FileCache::finish_rescan()`).
+///
+/// The thread stops once the returned sender is dropped along with the back-end watcher.
+fn spawn_rescan_worker(
+    file_cache: Arc<Mutex<FileCache>>,
+    inputs: SubscriptionInputs,
+) -> Option<std::sync::mpsc::Sender<Option<PathBuf>>> {
+    let (tx, rx) = std::sync::mpsc::channel::<Option<PathBuf>>();
+
+    let worker = move || {
+        while let Ok(path) = rx.recv() {
+            let paths: Vec<_> = std::iter::once(path).chain(rx.try_iter()).collect();
+
+            let Some(mut rescan) = file_cache.lock().ok().and_then(|cache| {
+                paths
+                    .iter()
+                    .map(|path| cache.start_rescan(path.as_deref()))
+                    .reduce(|mut rescan, other| {
+                        rescan.merge(other);
+                        rescan
+                    })
+            }) else {
+                return;
+            };
+
+            rescan.walk();
+
+            let Some(events) = file_cache.lock().ok().map(|mut cache| cache.finish_rescan(rescan)) else {
+                return;
+            };
+
+            let Ok(inputs) = inputs.read() else {
+                return;
+            };
+
+            for event in events {
+                dispatch(&inputs, &Ok(event));
             }
         }
-        NotifyErrorKind::Io(ref io_error) => match io_error.kind() {
-            IOErrorKind::NotFound => return PyFileNotFoundError::new_err(err_str),
-            IOErrorKind::PermissionDenied => return PyPermissionError::new_err(err_str),
-            _ => (),
-        },
-        _ => (),
     };
 
-    PyOSError::new_err(format!("{} ({:?})", err_str, notify_error))
+    match std::thread::Builder::new()
+        .name("notifykit-rescan".to_string())
+        .spawn(worker)
+    {
+        Ok(_) => Some(tx),
+        Err(e) => {
+            eprintln!("notifykit: failed to start the rescan worker: {e}");
+            None
+        }
+    }
+}
+
+/// `notify::Error` is not `Clone`, so every subscription gets a copy of the error with the same kind and paths.
+///
+/// I/O errors keep their OS error code (or kind and message), while generic errors keep their message.
+fn copy_error(error: &notify::Error) -> notify::Error {
+    let kind = match error.kind {
+        notify::ErrorKind::Generic(ref message) => notify::ErrorKind::Generic(message.clone()),
+        notify::ErrorKind::Io(ref err) => notify::ErrorKind::Io(match err.raw_os_error() {
+            Some(code) => std::io::Error::from_raw_os_error(code),
+            None => std::io::Error::new(err.kind(), err.to_string()),
+        }),
+        notify::ErrorKind::PathNotFound => notify::ErrorKind::PathNotFound,
+        notify::ErrorKind::WatchNotFound => notify::ErrorKind::WatchNotFound,
+        notify::ErrorKind::InvalidConfig(config) => notify::ErrorKind::InvalidConfig(config),
+        notify::ErrorKind::MaxFilesWatch => notify::ErrorKind::MaxFilesWatch,
+    };
+
+    notify::Error::new(kind).set_paths(error.paths.clone())
 }
 
-fn create_event(event: &RawEvent) -> Option<EventType> {
+/// Turn a raw event into a notifykit event.
+///
+/// The file cache (if the watcher keeps one) tells the type of renamed paths,
+/// which may have changed on disk since the event.
+pub fn create_event(event: &RawEvent, file_cache: Option<&FileCache>) -> Option<EventType> {
     let paths = &event.paths;
     let file_path: PathBuf = paths.first()?.to_owned();
 
@@ -263,11 +608,16 @@ fn create_event(event: &RawEvent) -> Option<EventType> {
                 EventType::ModifyMetadata(from_metadata_kind(file_path, metadata_kind))
             }
             ModifyKind::Data(data_kind) => EventType::ModifyData(from_data_kind(file_path, data_kind)),
+            // the two halves of renames within the watched paths are stitched together by `RenamePairing`,
+            // so the ones left are moves across the watch boundary
+            ModifyKind::Name(RenameMode::From) => EventType::MovedOut(MovedOutEvent::new(file_path)),
+            ModifyKind::Name(RenameMode::To) => EventType::MovedIn(MovedInEvent::new(file_path)),
             ModifyKind::Name(_) => {
-                // Debouncer stitches rename events, so rename_kind is not relevant
                 let target_path = paths.last().cloned()?;
 
-                return Some(EventType::Rename(from_rename_mode(file_path, target_path)));
+                let is_dir = file_cache.and_then(|cache| cache.is_dir(&target_path));
+
+                return Some(EventType::Rename(from_rename_mode(file_path, target_path, is_dir)));
             }
             ModifyKind::Other => EventType::ModifyOther(ModifyOtherEvent::new(file_path)),
             ModifyKind::Any => EventType::ModifyUnknown(ModifyUnknownEvent::new(file_path)),
@@ -278,3 +628,225 @@ fn create_event(event: &RawEvent) -> Option<EventType> {
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+    use std::time::Instant;
+
+    use notify::event::CreateKind;
+
+    use crate::events::base::ObjectType;
+    use crate::replay::read_recording;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("notifykit-watcher-{name}-{}", std::process::id()));
+
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        // the backend reports canonical paths
+        dir.canonicalize().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_watch_delivers_events() {
+        let root = temp_dir("delivers");
+        let mut watcher = Watcher::new(WatcherOptions {
+            delivery: DeliveryOptions {
+                buffering_time: Duration::from_millis(50),
+                ..DeliveryOptions::default()
+            },
+            ..WatcherOptions::default()
+        })
+        .unwrap();
+
+        watcher.watch(&[&root], true, false).unwrap();
+
+        let mut rx = watcher.subscribe();
+        watcher.start_drain(Duration::from_millis(20), Pipeline::default());
+
+        let target = root.join("created.txt");
+        fs::write(&target, "created").unwrap();
+
+        let batch = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("no events delivered")
+            .unwrap();
+
+        assert!(batch.iter().any(|event| matches!(
+            event,
+            EventType::Create(e) if e.path == target && e.file_type == ObjectType::File
+        )));
+
+        watcher.close();
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_backend_events() {
+        let root = temp_dir("backend");
+        let mut watcher = Watcher::new(WatcherOptions::default()).unwrap();
+
+        watcher.watch(&[&root], true, false).unwrap();
+        let mut rx = watcher.backend_events().unwrap();
+
+        let target = root.join("created.txt");
+        fs::write(&target, "created").unwrap();
+
+        // delivered right away, without waiting for the drain
+        let event = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("no backend events")
+            .unwrap();
+
+        assert_eq!(event.paths, vec![target]);
+
+        watcher.close();
+
+        // the rest of the events of the write are still there, but the stream ends after them
+        let rest = tokio::time::timeout(Duration::from_secs(5), async { while rx.recv().await.is_some() {} }).await;
+        assert!(rest.is_ok());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_rescan_worker_delivers_after_backend_events() {
+        let root = temp_dir("rescan-worker");
+        fs::create_dir_all(root.join("a")).unwrap();
+
+        let file_cache = Arc::new(Mutex::new(FileCache::new()));
+        file_cache.lock().unwrap().add_root(&root, RecursiveMode::Recursive);
+
+        let subscription = Subscription::new(
+            DeliveryOptions::default(),
+            16,
+            BackpressurePolicy::default(),
+            Arc::new(Mutex::new(Suppressor::new(RENAME_PAIRING_TIMEOUT))),
+        );
+        let inputs: SubscriptionInputs = Arc::new(RwLock::new(HashMap::from([(
+            DEFAULT_SUBSCRIPTION.to_string(),
+            subscription.input(),
+        )])));
+
+        let rescans = spawn_rescan_worker(Arc::clone(&file_cache), Arc::clone(&inputs)).unwrap();
+
+        // the back-end has missed this one
+        fs::write(root.join("a/missed.txt"), "missed").unwrap();
+
+        {
+            // the callback holds the cache while it requests rescans and handles the events that come after
+            let mut cache = file_cache.lock().unwrap();
+
+            rescans.send(Some(root.join("a"))).unwrap();
+            rescans.send(None).unwrap();
+
+            fs::write(root.join("reported.txt"), "reported").unwrap();
+            let reported = Event::new(EventKind::Create(CreateKind::File)).add_path(root.join("reported.txt"));
+            cache.handle_event(&reported);
+            dispatch(&inputs.read().unwrap(), &Ok(reported));
+        }
+
+        let input = inputs.read().unwrap()[DEFAULT_SUBSCRIPTION].clone();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut paths = Vec::new();
+
+        while paths.len() < 2 && Instant::now() < deadline {
+            let events = input.processor.lock().unwrap().flush_events();
+            paths.extend(events.into_iter().flat_map(|raw| raw.event.paths));
+
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        // the rescans have nothing to add to the reported file
+        std::thread::sleep(Duration::from_millis(100));
+        paths.extend(
+            input
+                .processor
+                .lock()
+                .unwrap()
+                .flush_events()
+                .into_iter()
+                .flat_map(|raw| raw.event.paths),
+        );
+
+        assert_eq!(paths, vec![root.join("reported.txt"), root.join("a/missed.txt")]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_record() {
+        let root = temp_dir("record");
+        let recording = std::env::temp_dir().join(format!("notifykit-watcher-recording-{}", std::process::id()));
+        let mut watcher = Watcher::new(WatcherOptions::default()).unwrap();
+
+        watcher.watch(&[&root], true, false).unwrap();
+        watcher.record(&recording).unwrap();
+
+        let target = root.join("created.txt");
+        fs::write(&target, "created").unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !read_recording(&recording).is_ok_and(|recorded| !recorded.is_empty()) && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        watcher.close();
+
+        let recorded = read_recording(&recording).unwrap();
+        assert_eq!(recorded[0].event.paths, vec![target]);
+
+        fs::remove_file(recording).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_copy_error() {
+        let path = PathBuf::from("/w/a");
+
+        let copy = copy_error(&notify::Error::path_not_found().add_path(path.clone()));
+        assert!(matches!(copy.kind, notify::ErrorKind::PathNotFound));
+        assert_eq!(copy.paths, vec![path.clone()]);
+
+        assert!(matches!(
+            copy_error(&notify::Error::new(notify::ErrorKind::MaxFilesWatch)).kind,
+            notify::ErrorKind::MaxFilesWatch
+        ));
+
+        let io_error = std::io::Error::from_raw_os_error(28);
+        let copy = copy_error(&notify::Error::io(io_error));
+        assert!(matches!(copy.kind, notify::ErrorKind::Io(ref err) if err.raw_os_error() == Some(28)));
+
+        let io_error = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+        let copy = copy_error(&notify::Error::io(io_error));
+        assert!(matches!(
+            copy.kind,
+            notify::ErrorKind::Io(ref err) if err.kind() == std::io::ErrorKind::PermissionDenied
+        ));
+        assert_eq!(copy.to_string(), "denied");
+    }
+
+    #[test]
+    fn test_closed_watcher() {
+        let mut watcher = Watcher::new(WatcherOptions::default()).unwrap();
+        watcher.close();
+
+        assert!(matches!(
+            watcher.watch(&[std::env::temp_dir()], true, false),
+            Err(WatchError::Closed)
+        ));
+        assert!(matches!(
+            watcher.remove_subscription(DEFAULT_SUBSCRIPTION),
+            Err(WatchError::DefaultSubscription)
+        ));
+        assert!(matches!(watcher.backend_events(), Err(WatchError::Closed)));
+        assert!(matches!(
+            watcher.record(std::env::temp_dir().join("unused")),
+            Err(WatchError::Closed)
+        ));
+    }
+}
@@ -74,7 +74,7 @@ def find_events(events: List[Event], event_type: type) -> List[Event]:
 def notifier():
     n = Notifier(debounce_ms=DEBOUNCE_MS, tick_ms=TICK_MS)
     yield n
-    n.stop()
+    n.close()
 
 
 @pytest.fixture
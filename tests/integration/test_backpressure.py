@@ -0,0 +1,100 @@
+"""Tests for backpressure policies applied when the consumer falls behind."""
+
+import asyncio
+from pathlib import Path
+
+import pytest
+
+from notifykit import CreateEvent, EventsLost, Notifier
+
+from .conftest import COLLECT_TIMEOUT, DEBOUNCE_MS, SETTLE_DELAY, TICK_MS, collect_events, has_event
+
+
+async def _write_in_separate_batches(directory: Path, count: int) -> list[Path]:
+    files = []
+
+    for i in range(count):
+        f = directory / f"file_{i}.txt"
+        f.write_text(f"content {i}")
+        files.append(f)
+
+        await asyncio.sleep(SETTLE_DELAY)
+
+    return files
+
+
+async def test_raise_policy(tmp_path: Path):
+    """A slow consumer gets EventsLost with the number of dropped batches."""
+    notifier = Notifier(debounce_ms=DEBOUNCE_MS, tick_ms=TICK_MS, event_buffer_size=1, backpressure="raise")
+
+    try:
+        await notifier.watch([tmp_path], recursive=True)
+        notifier.__aiter__()
+
+        await _write_in_separate_batches(tmp_path, 3)
+
+        with pytest.raises(EventsLost) as exc_info:
+            await asyncio.wait_for(notifier.__anext__(), timeout=COLLECT_TIMEOUT)
+
+        assert exc_info.value.count >= 1
+    finally:
+        notifier.close()
+
+
+async def test_drop_oldest_policy(tmp_path: Path):
+    """A slow consumer keeps getting the latest batches without EventsLost."""
+    notifier = Notifier(debounce_ms=DEBOUNCE_MS, tick_ms=TICK_MS, event_buffer_size=1, backpressure="drop_oldest")
+
+    try:
+        await notifier.watch([tmp_path], recursive=True)
+        notifier.__aiter__()
+
+        files = await _write_in_separate_batches(tmp_path, 3)
+        events = await collect_events(notifier)
+
+        assert has_event(events, CreateEvent, path=files[-1]), f"Missing the latest CreateEvent, got: {events}"
+    finally:
+        notifier.close()
+
+
+async def test_block_policy(tmp_path: Path):
+    """No batches are lost when the drain loop waits for the consumer."""
+    notifier = Notifier(debounce_ms=DEBOUNCE_MS, tick_ms=TICK_MS, event_buffer_size=1, backpressure="block")
+
+    try:
+        await notifier.watch([tmp_path], recursive=True)
+        notifier.__aiter__()
+
+        files = await _write_in_separate_batches(tmp_path, 3)
+        events = await collect_events(notifier)
+
+        for f in files:
+            assert has_event(events, CreateEvent, path=f), f"Missing CreateEvent for {f}, got: {events}"
+    finally:
+        notifier.close()
+
+
+async def test_coalesce_policy(tmp_path: Path):
+    """Pending batches are merged into one batch for a slow consumer."""
+    notifier = Notifier(debounce_ms=DEBOUNCE_MS, tick_ms=TICK_MS, event_buffer_size=1, backpressure="coalesce")
+
+    try:
+        await notifier.watch([tmp_path], recursive=True)
+        notifier.__aiter__()
+
+        files = await _write_in_separate_batches(tmp_path, 3)
+        batch = await asyncio.wait_for(notifier.__anext__(), timeout=COLLECT_TIMEOUT)
+
+        for f in files:
+            assert has_event(batch, CreateEvent, path=f), f"Missing CreateEvent for {f}, got: {batch}"
+
+        creates = [ev for ev in batch if isinstance(ev, CreateEvent)]
+        assert len(creates) == len({str(ev.path) for ev in creates}), f"Got duplicated events: {batch}"
+    finally:
+        notifier.close()
+
+
+def test_unknown_policy():
+    """Unknown policies are rejected."""
+    with pytest.raises(ValueError):
+        Notifier(backpressure="unknown")  # type: ignore[arg-type]
@@ -4,9 +4,20 @@ import asyncio
 from pathlib import Path
 from typing import List
 
-from notifykit import CreateEvent, Event, ModifyDataEvent, Notifier
+import pytest
 
-from .conftest import COLLECT_TIMEOUT, SETTLE_DELAY, SUBSEQUENT_TIMEOUT, collect_events, find_events, has_event
+from notifykit import CreateEvent, DirectoryChangedEvent, Event, ModifyDataEvent, Notifier, RescanEvent
+
+from .conftest import (
+    COLLECT_TIMEOUT,
+    DEBOUNCE_MS,
+    SETTLE_DELAY,
+    SUBSEQUENT_TIMEOUT,
+    TICK_MS,
+    collect_events,
+    find_events,
+    has_event,
+)
 
 
 async def test_multiple_events_batched(watched_dir: Path, notifier: Notifier):
@@ -66,3 +77,254 @@ async def test_rapid_changes_batched(watched_dir: Path, notifier: Notifier):
     assert len(batches) < num_writes, (
         f"Expected fewer batches than writes, got {len(batches)} batches for {num_writes} writes"
     )
+
+
+async def _write_continuously(target: Path, duration: float, interval: float = 0.03) -> None:
+    loop = asyncio.get_running_loop()
+    deadline = loop.time() + duration
+
+    i = 0
+    while loop.time() < deadline:
+        target.write_text(f"v{i}")
+        i += 1
+        await asyncio.sleep(interval)
+
+
+async def test_debounce_mode_waits_for_quiet_period(tmp_path: Path):
+    """In the debounce mode, a continuously written file is delivered once the writes stop."""
+    notifier = Notifier(debounce_ms=150, tick_ms=TICK_MS, mode="debounce")
+
+    try:
+        await notifier.watch([tmp_path], recursive=True)
+        await asyncio.sleep(0.05)
+        notifier.__aiter__()
+
+        target = tmp_path / "continuous.txt"
+        writer = asyncio.create_task(_write_continuously(target, duration=0.6))
+
+        early = await collect_events(notifier, timeout=0.5)
+        assert len(early) == 0, f"Expected no events while the file is changing, got: {early}"
+
+        await writer
+
+        batches = await _collect_batches(notifier)
+        assert len(batches) == 1, f"Expected a single batch after the writes stop, got: {batches}"
+        assert has_event(batches[0], ModifyDataEvent, path=target)
+    finally:
+        notifier.close()
+
+
+async def test_debounce_mode_max_wait(tmp_path: Path):
+    """max_wait_ms flushes events of a file that never stops changing."""
+    notifier = Notifier(debounce_ms=150, tick_ms=TICK_MS, mode="debounce", max_wait_ms=300)
+
+    try:
+        await notifier.watch([tmp_path], recursive=True)
+        await asyncio.sleep(0.05)
+        notifier.__aiter__()
+
+        target = tmp_path / "continuous.txt"
+        writer = asyncio.create_task(_write_continuously(target, duration=1.5))
+
+        try:
+            batch = await asyncio.wait_for(notifier.__anext__(), timeout=1.0)
+            assert not writer.done(), "Expected a batch before the writes stop"
+            assert has_event(batch, ModifyDataEvent, path=target)
+        finally:
+            await writer
+    finally:
+        notifier.close()
+
+
+async def test_leading_mode_delivers_first_event_immediately(tmp_path: Path):
+    """In the leading mode, the first change is delivered without waiting for the window."""
+    notifier = Notifier(debounce_ms=1000, tick_ms=1000, mode="leading")
+
+    try:
+        await notifier.watch([tmp_path], recursive=True)
+        await asyncio.sleep(0.05)
+        notifier.__aiter__()
+
+        target = tmp_path / "preview.md"
+        target.write_text("first save")
+
+        batch = await asyncio.wait_for(notifier.__anext__(), timeout=0.5)
+        assert has_event(batch, CreateEvent, path=target), f"Missing CreateEvent, got: {batch}"
+
+        target.write_text("second save")
+
+        events = await collect_events(notifier, timeout=0.5)
+        assert not has_event(events, ModifyDataEvent, path=target), f"Got events within the window: {events}"
+    finally:
+        notifier.close()
+
+
+async def test_throttle_mode_limits_batch_rate(tmp_path: Path):
+    """In the throttle mode, continuous changes produce at most one batch per window."""
+    notifier = Notifier(debounce_ms=300, tick_ms=TICK_MS, mode="throttle")
+
+    try:
+        await notifier.watch([tmp_path], recursive=True)
+        await asyncio.sleep(0.05)
+        notifier.__aiter__()
+
+        target = tmp_path / "continuous.txt"
+        await _write_continuously(target, duration=1.0)
+
+        batches = await _collect_batches(notifier, subsequent_timeout=0.5)
+
+        assert 2 <= len(batches) <= 5, f"Expected a batch per window, got {len(batches)} batches"
+    finally:
+        notifier.close()
+
+
+async def test_collapse_reduces_events_per_path(tmp_path: Path):
+    """With collapsing enabled, creating and writing a file in one batch is reported as a single CreateEvent."""
+    notifier = Notifier(debounce_ms=DEBOUNCE_MS, tick_ms=TICK_MS, collapse=True)
+
+    try:
+        await notifier.watch([tmp_path], recursive=True)
+        await asyncio.sleep(0.05)
+        notifier.__aiter__()
+
+        target = tmp_path / "collapsed.txt"
+        with open(target, "w") as f:
+            f.write("first")
+            f.flush()
+            f.write("second")
+
+        batch = await asyncio.wait_for(notifier.__anext__(), timeout=COLLECT_TIMEOUT)
+        target_events = [ev for ev in batch if Path(getattr(ev, "path", "")) == target]
+
+        assert len(target_events) == 1, f"Expected a single event for {target}, got: {target_events}"
+        assert isinstance(target_events[0], CreateEvent)
+    finally:
+        notifier.close()
+
+
+async def test_collapse_drops_short_lived_files(tmp_path: Path):
+    """With collapsing enabled, a file created and deleted within one batch is not reported."""
+    notifier = Notifier(debounce_ms=DEBOUNCE_MS, tick_ms=TICK_MS, collapse=True)
+
+    try:
+        await notifier.watch([tmp_path], recursive=True)
+        await asyncio.sleep(0.05)
+        notifier.__aiter__()
+
+        temp = tmp_path / "temp.txt"
+        temp.write_text("temp")
+        temp.unlink()
+        marker = tmp_path / "marker.txt"
+        marker.write_text("marker")
+
+        await asyncio.sleep(SETTLE_DELAY)
+        events = await collect_events(notifier)
+
+        assert has_event(events, CreateEvent, path=marker), f"Missing CreateEvent for {marker}, got: {events}"
+        assert not [ev for ev in events if Path(getattr(ev, "path", "")) == temp], f"Got events for {temp}: {events}"
+    finally:
+        notifier.close()
+
+
+async def test_max_batch_size_splits_batches(tmp_path: Path):
+    """Batches larger than max_batch_size are split, while no events are lost."""
+    notifier = Notifier(debounce_ms=DEBOUNCE_MS, tick_ms=TICK_MS, max_batch_size=2)
+
+    try:
+        await notifier.watch([tmp_path], recursive=True)
+        await asyncio.sleep(0.05)
+        notifier.__aiter__()
+
+        files = [tmp_path / f"file_{i}.txt" for i in range(5)]
+        for f in files:
+            f.write_text(f"content {f.name}")
+
+        batches = await _collect_batches(notifier)
+        events = [ev for batch in batches for ev in batch]
+
+        assert all(len(batch) <= 2 for batch in batches), f"Got batches over the limit: {batches}"
+        for f in files:
+            assert has_event(events, CreateEvent, path=f), f"Missing CreateEvent for {f}"
+    finally:
+        notifier.close()
+
+
+async def test_storm_threshold_emits_rescan(tmp_path: Path):
+    """A batch over the storm threshold is replaced with a single RescanEvent for the affected subtree."""
+    subtree = tmp_path / "node_modules"
+    subtree.mkdir()
+
+    notifier = Notifier(debounce_ms=DEBOUNCE_MS, tick_ms=TICK_MS, storm_threshold=10)
+
+    try:
+        await notifier.watch([tmp_path], recursive=True)
+        await asyncio.sleep(0.05)
+        notifier.__aiter__()
+
+        for i in range(30):
+            (subtree / f"module_{i}.js").write_text("module.exports = {}")
+
+        events = await collect_events(notifier)
+        rescans = find_events(events, RescanEvent)
+
+        assert rescans, f"Expected a RescanEvent, got: {events}"
+        assert all(str(ev.path) == str(subtree) for ev in rescans), f"Unexpected rescan paths: {rescans}"
+        assert not find_events(events, CreateEvent), f"Got individual events: {events}"
+    finally:
+        notifier.close()
+
+
+def test_invalid_max_batch_size():
+    """Batch size limits must be positive."""
+    notifier = Notifier(max_batch_size=0)
+
+    try:
+        with pytest.raises(ValueError):
+            notifier.__aiter__()
+    finally:
+        notifier.close()
+
+
+
+def test_invalid_tick():
+    """The drain interval must be positive."""
+    notifier = Notifier(tick_ms=0)
+
+    try:
+        with pytest.raises(ValueError):
+            notifier.__aiter__()
+
+        with pytest.raises(ValueError):
+            notifier.subscribe("slow", tick_ms=0)
+    finally:
+        notifier.close()
+
+
+async def test_aggregate_threshold_rolls_up_directory(tmp_path: Path):
+    """Many changes in one directory are rolled up into a DirectoryChangedEvent, other directories are kept as is."""
+    images = tmp_path / "img"
+    images.mkdir()
+
+    notifier = Notifier(debounce_ms=DEBOUNCE_MS, tick_ms=TICK_MS, aggregate_threshold=5)
+
+    try:
+        await notifier.watch([tmp_path], recursive=True)
+        await asyncio.sleep(0.05)
+        notifier.__aiter__()
+
+        for i in range(10):
+            (images / f"image_{i}.png").write_text("png")
+
+        readme = tmp_path / "README.md"
+        readme.write_text("readme")
+
+        events = await collect_events(notifier)
+        rolled_up = find_events(events, DirectoryChangedEvent)
+
+        assert rolled_up, f"Expected a DirectoryChangedEvent, got: {events}"
+        assert all(str(ev.path) == str(images) for ev in rolled_up), f"Unexpected directories: {rolled_up}"
+        assert "create" in rolled_up[0].kinds
+        assert not has_event(events, CreateEvent, path=images / "image_0.png"), f"Got per-file events: {events}"
+        assert has_event(events, CreateEvent, path=readme), f"Missing CreateEvent for {readme}, got: {events}"
+    finally:
+        notifier.close()
@@ -1,11 +1,12 @@
-"""Tests for directory-level events: create and delete."""
+"""Tests for directory-level events: create, delete and rename."""
 
 import asyncio
+import shutil
 from pathlib import Path
 
-from notifykit import CreateEvent, DeleteEvent, Notifier
+from notifykit import CreateEvent, DeleteEvent, Notifier, RenameEvent
 
-from .conftest import SETTLE_DELAY, collect_events, has_event
+from .conftest import DEBOUNCE_MS, SETTLE_DELAY, TICK_MS, collect_events, find_events, has_event
 
 
 async def test_dir_creation(watched_dir: Path, notifier: Notifier):
@@ -33,3 +34,82 @@ async def test_dir_deletion(watched_dir: Path, notifier: Notifier):
     events = await collect_events(notifier)
 
     assert has_event(events, DeleteEvent, path=subdir)
+
+
+async def test_dir_rename_children(tmp_path: Path):
+    """With expand_dir_renames, a directory rename lists old and new paths of its children."""
+    old_dir = tmp_path / "old"
+    new_dir = tmp_path / "new"
+    (old_dir / "nested").mkdir(parents=True)
+    (old_dir / "a.txt").write_text("a")
+    (old_dir / "nested" / "b.txt").write_text("b")
+
+    notifier = Notifier(debounce_ms=DEBOUNCE_MS, tick_ms=TICK_MS, expand_dir_renames=True)
+
+    try:
+        await notifier.watch([tmp_path], recursive=True)
+        await asyncio.sleep(0.05)
+        notifier.__aiter__()
+
+        old_dir.rename(new_dir)
+
+        await asyncio.sleep(SETTLE_DELAY)
+        events = await collect_events(notifier)
+        renames = [ev for ev in find_events(events, RenameEvent) if str(ev.old_path) == str(old_dir)]
+
+        assert renames, f"Missing RenameEvent for {old_dir}, got: {events}"
+        assert renames[0].is_dir
+        assert [(str(old), str(new)) for old, new in renames[0].children] == [
+            (str(old_dir / "a.txt"), str(new_dir / "a.txt")),
+            (str(old_dir / "nested"), str(new_dir / "nested")),
+            (str(old_dir / "nested" / "b.txt"), str(new_dir / "nested" / "b.txt")),
+        ]
+    finally:
+        notifier.close()
+
+
+async def test_dir_rename_without_children(watched_dir: Path, notifier: Notifier):
+    """Directory renames are not expanded by default."""
+    old_dir = watched_dir / "old"
+    old_dir.mkdir()
+    (old_dir / "a.txt").write_text("a")
+
+    await asyncio.sleep(SETTLE_DELAY)
+    await collect_events(notifier)  # drain
+
+    old_dir.rename(watched_dir / "new")
+
+    await asyncio.sleep(SETTLE_DELAY)
+    events = await collect_events(notifier)
+
+    for ev in find_events(events, RenameEvent):
+        assert ev.children == [], f"Got expanded rename: {ev}"
+
+
+async def test_dir_delete_children(tmp_path: Path):
+    """With expand_dir_deletes, every child of a removed tree gets a single DeleteEvent before its directory."""
+    tree = tmp_path / "tree"
+    (tree / "nested").mkdir(parents=True)
+    (tree / "a.txt").write_text("a")
+    (tree / "nested" / "b.txt").write_text("b")
+
+    notifier = Notifier(debounce_ms=DEBOUNCE_MS, tick_ms=TICK_MS, expand_dir_deletes=True)
+
+    try:
+        await notifier.watch([tmp_path], recursive=True)
+        await asyncio.sleep(0.05)
+        notifier.__aiter__()
+
+        shutil.rmtree(tree)
+
+        await asyncio.sleep(SETTLE_DELAY)
+        events = await collect_events(notifier)
+        deleted = [str(ev.path) for ev in find_events(events, DeleteEvent)]
+
+        for path in (tree / "a.txt", tree / "nested" / "b.txt", tree / "nested", tree):
+            assert deleted.count(str(path)) == 1, f"Expected a single DeleteEvent for {path}, got: {events}"
+
+        assert deleted.index(str(tree / "nested" / "b.txt")) < deleted.index(str(tree / "nested"))
+        assert deleted.index(str(tree / "nested")) < deleted.index(str(tree))
+    finally:
+        notifier.close()
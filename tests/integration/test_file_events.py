@@ -1,4 +1,4 @@
-"""Tests for basic file-level events: create, modify, delete, rename."""
+"""Tests for basic file-level events: create, modify, delete, rename, move."""
 
 import asyncio
 from pathlib import Path
@@ -8,11 +8,13 @@ from notifykit import (
     DeleteEvent,
     ModifyDataEvent,
     ModifyMetadataEvent,
+    MovedInEvent,
+    MovedOutEvent,
     Notifier,
     RenameEvent,
 )
 
-from .conftest import SETTLE_DELAY, collect_events, find_events, has_event
+from .conftest import DEBOUNCE_MS, SETTLE_DELAY, TICK_MS, collect_events, find_events, has_event
 
 
 async def test_file_creation(watched_dir: Path, notifier: Notifier):
@@ -78,3 +80,91 @@ async def test_file_rename(watched_dir: Path, notifier: Notifier):
     got_delete_create = has_event(events, DeleteEvent, path=src) and has_event(events, CreateEvent, path=dst)
 
     assert got_rename or got_delete_create, f"Expected rename or delete+create, got: {events}"
+
+
+async def _watch_subdir(notifier: Notifier, tmp_path: Path) -> tuple[Path, Path]:
+    """Watch a subdirectory of tmp_path, so files can be moved across the watch boundary."""
+    watched = tmp_path / "watched"
+    outside = tmp_path / "outside"
+    watched.mkdir()
+    outside.mkdir()
+
+    await notifier.watch([watched], recursive=True)
+    await asyncio.sleep(0.05)
+
+    return watched, outside
+
+
+async def test_file_rename_has_both_paths(tmp_path: Path):
+    """A rename within the watched paths is reported as one RenameEvent with distinct paths."""
+    notifier = Notifier(debounce_ms=DEBOUNCE_MS, tick_ms=TICK_MS)
+
+    try:
+        watched, _ = await _watch_subdir(notifier, tmp_path)
+        src = watched / "old_name.txt"
+        dst = watched / "new_name.txt"
+        src.write_text("rename me")
+
+        await asyncio.sleep(SETTLE_DELAY)
+        await collect_events(notifier)  # drain
+
+        src.rename(dst)
+
+        await asyncio.sleep(SETTLE_DELAY)
+        events = await collect_events(notifier)
+
+        for ev in find_events(events, RenameEvent):
+            assert str(ev.old_path) != str(ev.new_path), f"Got a rename with identical paths: {events}"
+        assert not find_events(events, MovedInEvent) and not find_events(events, MovedOutEvent), f"Got moves: {events}"
+    finally:
+        notifier.close()
+
+
+async def test_file_moved_out(tmp_path: Path):
+    """MovedOutEvent emitted when a file is moved to an unwatched location."""
+    notifier = Notifier(debounce_ms=DEBOUNCE_MS, tick_ms=TICK_MS)
+
+    try:
+        watched, outside = await _watch_subdir(notifier, tmp_path)
+        src = watched / "leaving.txt"
+        src.write_text("bye")
+
+        await asyncio.sleep(SETTLE_DELAY)
+        await collect_events(notifier)  # drain
+
+        src.rename(outside / "leaving.txt")
+
+        await asyncio.sleep(SETTLE_DELAY)
+        events = await collect_events(notifier)
+
+        got_move = has_event(events, MovedOutEvent, path=src)
+        got_delete = has_event(events, DeleteEvent, path=src)
+
+        assert got_move or got_delete, f"Expected MovedOutEvent, got: {events}"
+        assert not find_events(events, RenameEvent), f"Got a rename: {events}"
+    finally:
+        notifier.close()
+
+
+async def test_file_moved_in(tmp_path: Path):
+    """MovedInEvent emitted when a file is moved in from an unwatched location."""
+    notifier = Notifier(debounce_ms=DEBOUNCE_MS, tick_ms=TICK_MS)
+
+    try:
+        watched, outside = await _watch_subdir(notifier, tmp_path)
+        src = outside / "arriving.txt"
+        dst = watched / "arriving.txt"
+        src.write_text("hi")
+
+        src.rename(dst)
+
+        await asyncio.sleep(SETTLE_DELAY)
+        events = await collect_events(notifier)
+
+        got_move = has_event(events, MovedInEvent, path=dst)
+        got_create = has_event(events, CreateEvent, path=dst)
+
+        assert got_move or got_create, f"Expected MovedInEvent, got: {events}"
+        assert not find_events(events, RenameEvent), f"Got a rename: {events}"
+    finally:
+        notifier.close()
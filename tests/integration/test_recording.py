@@ -0,0 +1,30 @@
+"""Tests for recording raw backend events."""
+
+import asyncio
+import json
+from pathlib import Path
+
+import pytest
+
+from notifykit import Notifier
+
+from .conftest import SETTLE_DELAY, collect_events
+
+
+async def test_record_backend_events(watched_dir: Path, notifier: Notifier, tmp_path_factory: pytest.TempPathFactory):
+    """Raw events are written to the recording with the time they arrived at."""
+    # outside of the watched directory, so the recording doesn't record its own writes
+    recording = tmp_path_factory.mktemp("recording") / "events.ndjson"
+    notifier.record(recording)
+
+    target = watched_dir / "recorded.txt"
+    target.write_text("recorded")
+
+    await asyncio.sleep(SETTLE_DELAY)
+    await collect_events(notifier)
+    notifier.close()
+
+    lines = [json.loads(line) for line in recording.read_text().splitlines()]
+
+    assert any(line["event"]["paths"] == [str(target)] for line in lines)
+    assert [line["elapsed_us"] for line in lines] == sorted(line["elapsed_us"] for line in lines)
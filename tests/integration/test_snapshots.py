@@ -0,0 +1,123 @@
+"""Tests for detecting changes made while the application was not running."""
+
+from pathlib import Path
+
+import pytest
+
+from notifykit import CreateEvent, DeleteEvent, EventFilter, ModifyDataEvent, Notifier, RenameEvent, Snapshot
+
+from .conftest import has_event
+
+
+async def test_changes_since_snapshot(tmp_path: Path):
+    """Changes made between two runs are reported as events."""
+    watched = tmp_path / "watched"
+    watched.mkdir()
+    snapshot = tmp_path / "snapshot.json"
+
+    (watched / "changed.txt").write_text("v1")
+    (watched / "removed.txt").write_text("removed")
+    (watched / "moved.txt").write_text("moved")
+
+    notifier = Notifier()
+    try:
+        await notifier.watch([watched])
+        notifier.save_snapshot(snapshot)
+    finally:
+        notifier.close()
+
+    (watched / "changed.txt").write_text("version 2")
+    (watched / "new.txt").write_text("new")
+    (watched / "removed.txt").unlink()
+    (watched / "moved.txt").rename(watched / "renamed.txt")
+
+    notifier = Notifier()
+    try:
+        await notifier.watch([watched])
+        events = notifier.changes_since(snapshot)
+    finally:
+        notifier.close()
+
+    assert has_event(events, CreateEvent, path=watched / "new.txt"), f"Missing CreateEvent, got: {events}"
+    assert has_event(events, DeleteEvent, path=watched / "removed.txt"), f"Missing DeleteEvent, got: {events}"
+    assert has_event(events, ModifyDataEvent, path=watched / "changed.txt"), f"Missing ModifyDataEvent, got: {events}"
+    assert has_event(
+        events, RenameEvent, old_path=watched / "moved.txt", new_path=watched / "renamed.txt"
+    ), f"Missing RenameEvent, got: {events}"
+
+
+async def test_changes_since_missing_snapshot(tmp_path: Path):
+    """A missing snapshot file raises FileNotFoundError."""
+    notifier = Notifier()
+
+    try:
+        await notifier.watch([tmp_path])
+
+        with pytest.raises(FileNotFoundError):
+            notifier.changes_since(tmp_path / "missing.json")
+    finally:
+        notifier.close()
+
+
+def test_snapshot_diff(tmp_path: Path):
+    """Diffing two snapshots reports the changes made between them."""
+    (tmp_path / "changed.txt").write_text("v1")
+    (tmp_path / "removed.txt").write_text("removed")
+    (tmp_path / "moved.txt").write_text("moved")
+
+    before = Snapshot.take(tmp_path)
+
+    (tmp_path / "changed.txt").write_text("version 2")
+    (tmp_path / "new.txt").write_text("new")
+    (tmp_path / "removed.txt").unlink()
+    (tmp_path / "moved.txt").rename(tmp_path / "renamed.txt")
+
+    events = before.diff(Snapshot.take(tmp_path))
+
+    assert has_event(events, CreateEvent, path=tmp_path / "new.txt"), f"Missing CreateEvent, got: {events}"
+    assert has_event(events, DeleteEvent, path=tmp_path / "removed.txt"), f"Missing DeleteEvent, got: {events}"
+    assert has_event(events, ModifyDataEvent, path=tmp_path / "changed.txt"), f"Missing ModifyDataEvent, got: {events}"
+    assert has_event(
+        events, RenameEvent, old_path=tmp_path / "moved.txt", new_path=tmp_path / "renamed.txt"
+    ), f"Missing RenameEvent, got: {events}"
+
+
+def test_snapshot_filter(tmp_path: Path):
+    """Paths excluded by the filter are not captured."""
+
+    class ModulesFilter(EventFilter):
+        ignore_dirs = ("node_modules",)
+
+    (tmp_path / "node_modules").mkdir()
+    (tmp_path / "node_modules" / "index.js").write_text("module.exports = {}")
+    (tmp_path / "main.js").write_text("main")
+
+    before = Snapshot.take(tmp_path, filter=ModulesFilter())
+    assert len(before) == 2  # the root and main.js
+
+    (tmp_path / "node_modules" / "util.js").write_text("module.exports = {}")
+
+    assert before.diff(Snapshot.take(tmp_path, filter=ModulesFilter())) == []
+
+
+def test_snapshot_save_load(tmp_path: Path):
+    """A saved snapshot can be loaded back and diffed against a fresh one."""
+    watched = tmp_path / "watched"
+    watched.mkdir()
+    (watched / "a.txt").write_text("a")
+
+    Snapshot.take(watched).save(tmp_path / "snapshot.json")
+    (watched / "b.txt").write_text("b")
+
+    loaded = Snapshot.load(tmp_path / "snapshot.json")
+    events = loaded.diff(Snapshot.take(watched))
+
+    assert len(loaded) == 2
+    assert has_event(events, CreateEvent, path=watched / "b.txt"), f"Missing CreateEvent, got: {events}"
+    assert not has_event(events, CreateEvent, path=watched / "a.txt"), f"Got events for unchanged files: {events}"
+
+
+def test_snapshot_missing_root(tmp_path: Path):
+    """Taking a snapshot of a missing directory raises FileNotFoundError."""
+    with pytest.raises(FileNotFoundError):
+        Snapshot.take(tmp_path / "missing")
@@ -0,0 +1,79 @@
+"""Tests for multiple named subscriptions sharing one watcher."""
+
+import asyncio
+from pathlib import Path
+
+import pytest
+
+from notifykit import CreateEvent, EventFilter, Notifier, WatcherError
+
+from .conftest import COLLECT_TIMEOUT, SETTLE_DELAY, collect_events, has_event
+
+
+class LogFilter(EventFilter):
+    ignore_object_patterns = (r"\.log$",)
+
+
+async def test_subscriptions_have_own_filters(watched_dir: Path, notifier: Notifier):
+    """Each subscription applies its own filter to the same events."""
+    everything = notifier.subscribe("everything")
+    no_logs = notifier.subscribe("no_logs", filter=LogFilter())
+
+    log_file = watched_dir / "app.log"
+    src_file = watched_dir / "app.py"
+    log_file.write_text("log")
+    src_file.write_text("src")
+
+    await asyncio.sleep(SETTLE_DELAY)
+    all_events = await collect_events(everything)
+    filtered_events = await collect_events(no_logs)
+
+    assert has_event(all_events, CreateEvent, path=log_file), f"Missing log event, got: {all_events}"
+    assert has_event(all_events, CreateEvent, path=src_file), f"Missing src event, got: {all_events}"
+
+    assert not has_event(filtered_events, CreateEvent, path=log_file), f"Got log event: {filtered_events}"
+    assert has_event(filtered_events, CreateEvent, path=src_file), f"Missing src event, got: {filtered_events}"
+
+
+async def test_subscriptions_do_not_affect_default_stream(watched_dir: Path, notifier: Notifier):
+    """Iterating the notifier itself keeps working alongside named subscriptions."""
+    sub = notifier.subscribe("extra")
+
+    target = watched_dir / "shared.txt"
+    target.write_text("shared")
+
+    await asyncio.sleep(SETTLE_DELAY)
+    default_events = await collect_events(notifier)
+    sub_events = await collect_events(sub)
+
+    assert has_event(default_events, CreateEvent, path=target), f"Missing default event, got: {default_events}"
+    assert has_event(sub_events, CreateEvent, path=target), f"Missing subscription event, got: {sub_events}"
+
+
+async def test_subscription_own_debounce(watched_dir: Path, notifier: Notifier):
+    """A subscription with a long debounce doesn't deliver events early."""
+    slow = notifier.subscribe("slow", debounce_ms=2000)
+
+    (watched_dir / "file.txt").write_text("content")
+
+    await asyncio.sleep(SETTLE_DELAY)
+    events = await collect_events(slow, timeout=0.5)
+
+    assert len(events) == 0, f"Expected no events before debounce, got: {events}"
+
+
+async def test_subscription_close(watched_dir: Path, notifier: Notifier):
+    """Closing a subscription ends its iteration."""
+    sub = notifier.subscribe("closing")
+    sub.close()
+
+    with pytest.raises(StopAsyncIteration):
+        await asyncio.wait_for(sub.__anext__(), timeout=COLLECT_TIMEOUT)
+
+
+async def test_duplicate_subscription(notifier: Notifier):
+    """Subscription names are unique per notifier."""
+    notifier.subscribe("dup")
+
+    with pytest.raises(WatcherError):
+        notifier.subscribe("dup")
@@ -0,0 +1,101 @@
+"""Tests for suppressing events caused by the application's own writes."""
+
+import asyncio
+from pathlib import Path
+
+from notifykit import CreateEvent, ModifyDataEvent, Notifier
+
+from .conftest import SETTLE_DELAY, TICK_MS, collect_events, has_event
+
+
+async def test_suppress_drops_own_writes(watched_dir: Path, notifier: Notifier):
+    """Writes made inside suppress() are not reported, other writes are."""
+    generated = watched_dir / "generated.py"
+    foreign = watched_dir / "foreign.py"
+
+    with notifier.suppress([generated]):
+        generated.write_text("# generated")
+
+    foreign.write_text("# written by someone else")
+
+    await asyncio.sleep(SETTLE_DELAY)
+    events = await collect_events(notifier)
+
+    assert has_event(events, CreateEvent, path=foreign), f"Missing foreign event, got: {events}"
+    assert not any(str(getattr(ev, "path", "")) == str(generated) for ev in events), f"Got own events: {events}"
+
+
+async def test_suppress_directory(watched_dir: Path, notifier: Notifier):
+    """Suppressing a directory covers files inside it."""
+    out_dir = watched_dir / "out"
+    out_dir.mkdir()
+
+    await asyncio.sleep(SETTLE_DELAY)
+    await collect_events(notifier)  # drain mkdir event
+
+    with notifier.suppress([out_dir]):
+        (out_dir / "a.txt").write_text("a")
+        (out_dir / "b.txt").write_text("b")
+
+    await asyncio.sleep(SETTLE_DELAY)
+    events = await collect_events(notifier, timeout=0.5)
+
+    assert len(events) == 0, f"Expected no events for suppressed dir, got: {events}"
+
+
+async def test_suppress_is_released(watched_dir: Path, notifier: Notifier):
+    """Writes after the suppress() block are reported again."""
+    target = watched_dir / "target.txt"
+
+    with notifier.suppress([target]):
+        target.write_text("ours")
+
+    await asyncio.sleep(SETTLE_DELAY)
+    await collect_events(notifier, timeout=0.5)
+
+    target.unlink()
+    target.write_text("theirs")
+
+    await asyncio.sleep(SETTLE_DELAY)
+    events = await collect_events(notifier)
+
+    assert has_event(events, CreateEvent, path=target), f"Missing event after release, got: {events}"
+
+
+async def test_expect_write(watched_dir: Path, notifier: Notifier):
+    """expect_write() drops events for the path within the timeout."""
+    target = watched_dir / "formatted.py"
+
+    notifier.expect_write([target], timeout_ms=500)
+    target.write_text("formatted")
+
+    await asyncio.sleep(SETTLE_DELAY)
+    events = await collect_events(notifier, timeout=0.5)
+
+    assert len(events) == 0, f"Expected no events for expected write, got: {events}"
+
+
+async def test_suppress_while_debouncing(tmp_path: Path):
+    """Own writes are dropped even if debouncing holds them back for longer than the suppression window."""
+    generated = tmp_path / "generated.py"
+    notifier = Notifier(debounce_ms=300, tick_ms=TICK_MS, mode="debounce")
+
+    try:
+        await notifier.watch([tmp_path], recursive=True)
+        await asyncio.sleep(0.05)
+        notifier.__aiter__()
+
+        with notifier.suppress([generated]):
+            generated.write_text("# generated")
+
+        # someone else keeps changing the file, so its events are held back long after the window is closed
+        for i in range(16):
+            await asyncio.sleep(0.1)
+            generated.write_text(f"# edited {i}")
+
+        events = await collect_events(notifier)
+
+        assert has_event(events, ModifyDataEvent, path=generated), f"Missing foreign writes, got: {events}"
+        assert not has_event(events, CreateEvent, path=generated), f"Got own events: {events}"
+    finally:
+        notifier.close()
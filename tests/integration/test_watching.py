@@ -6,9 +6,9 @@ from pathlib import Path
 
 import pytest
 
-from notifykit import CreateEvent, Notifier
+from notifykit import CreateEvent, Notifier, WatcherError
 
-from .conftest import DEBOUNCE_MS, SETTLE_DELAY, TICK_MS, collect_events, has_event
+from .conftest import COLLECT_TIMEOUT, DEBOUNCE_MS, SETTLE_DELAY, TICK_MS, collect_events, has_event
 
 
 async def test_recursive_watching(watched_dir: Path, notifier: Notifier):
@@ -135,3 +135,51 @@ async def test_watch_multiple_paths(tmp_path: Path):
         assert has_event(events, CreateEvent, path=file_b), f"Missing event from dir_b, got: {events}"
     finally:
         notifier.stop()
+
+
+async def test_stop_flush_delivers_pending_events(tmp_path: Path):
+    """stop(flush=True) delivers events still inside the debounce window, then ends iteration."""
+    notifier = Notifier(debounce_ms=5000, tick_ms=TICK_MS)
+
+    await notifier.watch([tmp_path], recursive=True)
+    await asyncio.sleep(0.05)
+
+    aiter = notifier.__aiter__()
+
+    target = tmp_path / "last_edit.txt"
+    target.write_text("before shutdown")
+
+    await asyncio.sleep(SETTLE_DELAY)
+    notifier.stop(flush=True)
+
+    events = await asyncio.wait_for(aiter.__anext__(), timeout=COLLECT_TIMEOUT)
+    assert has_event(events, CreateEvent, path=target), f"Missing pending event, got: {events}"
+
+    with pytest.raises(StopAsyncIteration):
+        await asyncio.wait_for(aiter.__anext__(), timeout=COLLECT_TIMEOUT)
+
+
+async def test_async_context_manager(tmp_path: Path):
+    """Leaving `async with` closes the notifier and ends iteration."""
+    async with Notifier(debounce_ms=DEBOUNCE_MS, tick_ms=TICK_MS) as notifier:
+        await notifier.watch([tmp_path], recursive=True)
+        await asyncio.sleep(0.05)
+
+        probe = tmp_path / "probe.txt"
+        probe.write_text("probe")
+
+        await asyncio.sleep(SETTLE_DELAY)
+        events = await collect_events(notifier)
+        assert has_event(events, CreateEvent, path=probe)
+
+    with pytest.raises(StopAsyncIteration):
+        await asyncio.wait_for(notifier.__anext__(), timeout=COLLECT_TIMEOUT)
+
+
+async def test_watch_after_close(tmp_path: Path):
+    """A closed notifier can't be used to watch paths anymore."""
+    notifier = Notifier(debounce_ms=DEBOUNCE_MS, tick_ms=TICK_MS)
+    notifier.close()
+
+    with pytest.raises(WatcherError):
+        await notifier.watch([tmp_path])
@@ -0,0 +1,103 @@
+"""Tests for value semantics of event classes: equality, hashing and pickling."""
+
+import copy
+import pickle
+
+import pytest
+
+from notifykit import (
+    AccessEvent,
+    AccessMode,
+    AccessType,
+    CreateEvent,
+    DataType,
+    DeleteEvent,
+    DirectoryChangedEvent,
+    Event,
+    MetadataType,
+    ModifyDataEvent,
+    ModifyMetadataEvent,
+    ModifyOtherEvent,
+    ModifyUnknownEvent,
+    MovedInEvent,
+    MovedOutEvent,
+    ObjectType,
+    RenameEvent,
+    RescanEvent,
+)
+
+EVENTS = [
+    AccessEvent("/tmp/app.py", AccessType.OPEN, AccessMode.READ),
+    CreateEvent("/tmp/app.py", ObjectType.FILE),
+    DeleteEvent("/tmp/old", ObjectType.DIR),
+    ModifyDataEvent("/tmp/app.py", DataType.CONTENT),
+    ModifyMetadataEvent("/tmp/app.py", MetadataType.ACCESS_TIME),
+    ModifyOtherEvent("/tmp/app.py"),
+    ModifyUnknownEvent("/tmp/app.py"),
+    RenameEvent("/tmp/a", "/tmp/b", True, [("/tmp/a/1.txt", "/tmp/b/1.txt")]),
+    MovedInEvent("/tmp/in.txt"),
+    MovedOutEvent("/tmp/out.txt"),
+    RescanEvent("/tmp/node_modules", 1000),
+    DirectoryChangedEvent("/tmp/img", 20, ["create", "modify_data"]),
+]
+
+
+def test__eq__same_fields() -> None:
+    assert CreateEvent("/tmp/app.py", ObjectType.FILE) == CreateEvent("/tmp/app.py", ObjectType.FILE)
+    assert RenameEvent("/tmp/a", "/tmp/b") == RenameEvent("/tmp/a", "/tmp/b", False, [])
+
+
+def test__eq__different_fields() -> None:
+    assert CreateEvent("/tmp/app.py", ObjectType.FILE) != CreateEvent("/tmp/app.py", ObjectType.DIR)
+    assert AccessEvent("/tmp/app.py", AccessType.OPEN, AccessMode.READ) != AccessEvent(
+        "/tmp/app.py", AccessType.OPEN, None
+    )
+    assert DirectoryChangedEvent("/tmp/img", 20, ["create"]) != DirectoryChangedEvent("/tmp/img", 20, ["delete"])
+
+
+def test__eq__different_classes() -> None:
+    assert CreateEvent("/tmp/app.py", ObjectType.FILE) != DeleteEvent("/tmp/app.py", ObjectType.FILE)
+    assert MovedInEvent("/tmp/app.py") != MovedOutEvent("/tmp/app.py")
+    assert ModifyOtherEvent("/tmp/app.py") != "/tmp/app.py"
+
+
+def test__hash__deduplicates_events() -> None:
+    events = [
+        ModifyDataEvent("/tmp/app.py", DataType.CONTENT),
+        ModifyDataEvent("/tmp/app.py", DataType.CONTENT),
+        ModifyMetadataEvent("/tmp/app.py", MetadataType.PERMISSIONS),
+        ModifyUnknownEvent("/tmp/app.py"),
+        ModifyUnknownEvent("/tmp/app.py"),
+    ]
+
+    assert len(set(events)) == 3
+
+    counts = {RescanEvent("/tmp/node_modules", 10): 1}
+    assert counts[RescanEvent("/tmp/node_modules", 10)] == 1
+
+
+@pytest.mark.parametrize("event", EVENTS, ids=repr)
+def test__pickle__round_trip(event: Event) -> None:
+    for protocol in range(pickle.HIGHEST_PROTOCOL + 1):
+        restored = pickle.loads(pickle.dumps(event, protocol=protocol))
+
+        assert restored == event
+        assert hash(restored) == hash(event)
+
+
+def test__pickle__enums() -> None:
+    for enum in (ObjectType, AccessType, AccessMode, DataType, MetadataType):
+        names = [name for name in dir(enum) if name.isupper()]
+        assert names
+
+        for name in names:
+            value = getattr(enum, name)
+
+            assert pickle.loads(pickle.dumps(value)) is value
+
+
+def test__copy__events() -> None:
+    event = RenameEvent("/tmp/a", "/tmp/b", True, [("/tmp/a/1.txt", "/tmp/b/1.txt")])
+
+    assert copy.copy(event) == event
+    assert copy.deepcopy(event) == event
@@ -0,0 +1,110 @@
+"""Tests for serializing events to dicts and JSON and back."""
+
+import json
+from pathlib import Path
+from typing import List
+
+import pytest
+
+from notifykit import (
+    AccessEvent,
+    AccessMode,
+    AccessType,
+    CreateEvent,
+    DataType,
+    DeleteEvent,
+    DirectoryChangedEvent,
+    Event,
+    MetadataType,
+    ModifyDataEvent,
+    ModifyMetadataEvent,
+    ModifyOtherEvent,
+    ModifyUnknownEvent,
+    MovedInEvent,
+    MovedOutEvent,
+    ObjectType,
+    RenameEvent,
+    RescanEvent,
+    event_from_dict,
+    event_from_json,
+)
+
+EVENTS: List[Event] = [
+    AccessEvent("/tmp/app.py", AccessType.OPEN, AccessMode.READ),
+    AccessEvent("/tmp/app.py", AccessType.READ, None),
+    CreateEvent("/tmp/app.py", ObjectType.FILE),
+    DeleteEvent("/tmp/old", ObjectType.DIR),
+    ModifyDataEvent("/tmp/app.py", DataType.CONTENT),
+    ModifyMetadataEvent("/tmp/app.py", MetadataType.ACCESS_TIME),
+    ModifyOtherEvent("/tmp/app.py"),
+    ModifyUnknownEvent("/tmp/app.py"),
+    RenameEvent("/tmp/a", "/tmp/b", True, [("/tmp/a/1.txt", "/tmp/b/1.txt")]),
+    MovedInEvent("/tmp/in.txt"),
+    MovedOutEvent("/tmp/out.txt"),
+    RescanEvent("/tmp/node_modules", 1000),
+    DirectoryChangedEvent("/tmp/img", 20, ["create", "modify_data"]),
+]
+
+
+@pytest.mark.parametrize("event", EVENTS, ids=repr)
+def test__serialize__round_trip(event: Event) -> None:
+    restored = type(event).from_dict(event.to_dict())
+
+    assert repr(restored) == repr(event)
+    assert restored.to_dict() == event.to_dict()
+    assert type(event).from_json(event.to_json()).to_dict() == event.to_dict()
+    assert event_from_json(event.to_json()).to_dict() == event.to_dict()
+
+
+def test__serialize__schema() -> None:
+    assert CreateEvent("/tmp/app.py", ObjectType.FILE).to_dict() == {
+        "kind": "create",
+        "path": "/tmp/app.py",
+        "file_type": "FILE",
+    }
+    assert json.loads(RenameEvent("/tmp/a", "/tmp/b").to_json()) == {
+        "kind": "rename",
+        "old_path": "/tmp/a",
+        "new_path": "/tmp/b",
+        "is_dir": False,
+        "children": [],
+    }
+    assert ModifyMetadataEvent("/tmp/app.py", MetadataType.WRITE_TIME).to_dict()["metadata_type"] == "WRITE_TIME"
+
+
+def test__deserialize__any_kind() -> None:
+    event = event_from_dict({"kind": "delete", "path": Path("/tmp/old"), "file_type": "DIR"})
+
+    assert isinstance(event, DeleteEvent)
+    assert Path(event.path) == Path("/tmp/old")
+    assert event.file_type == ObjectType.DIR
+
+
+def test__deserialize__optional_rename_fields() -> None:
+    event = RenameEvent.from_dict({"kind": "rename", "old_path": "/tmp/a", "new_path": "/tmp/b"})
+
+    assert not event.is_dir
+    assert event.children == []
+
+
+def test__deserialize__unexpected_kind() -> None:
+    with pytest.raises(ValueError, match="delete"):
+        CreateEvent.from_dict({"kind": "delete", "path": "/tmp/old", "file_type": "DIR"})
+
+
+@pytest.mark.parametrize(
+    "data",
+    [
+        {"path": "/tmp/app.py", "file_type": "FILE"},
+        {"kind": "teleport", "path": "/tmp/app.py"},
+        {"kind": "create", "path": "/tmp/app.py", "file_type": "SYMLINK"},
+    ],
+)
+def test__deserialize__invalid_data(data: dict) -> None:
+    with pytest.raises(ValueError):
+        event_from_dict(data)
+
+
+def test__deserialize__invalid_json() -> None:
+    with pytest.raises(ValueError):
+        event_from_json("{not json")